@@ -0,0 +1,137 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{project::Project, tag::Tag, task::Task, traits::Indexable};
+
+/// Hourly rate in the given currency.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Rate {
+  pub per_hour: f64,
+  pub currency: String,
+}
+
+impl Rate {
+  pub fn amount(&self, duration: chrono::Duration) -> f64 {
+    self.per_hour * duration.num_seconds() as f64 / 3600.0
+  }
+}
+
+/// Sums of money per currency, rates in different currencies are never
+/// converted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Money(BTreeMap<String, f64>);
+
+impl Money {
+  pub fn add(&mut self, rate: &Rate, duration: chrono::Duration) {
+    *self.0.entry(rate.currency.clone()).or_default() += rate.amount(duration);
+  }
+
+  pub fn merge(&mut self, other: &Money) {
+    for (currency, amount) in other.0.iter() {
+      *self.0.entry(currency.clone()).or_default() += amount;
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+impl Display for Money {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let amounts: Vec<String> = self
+      .0
+      .iter()
+      .map(|(currency, amount)| format!("{amount:.2} {currency}"))
+      .collect();
+    write!(f, "{}", amounts.join(", "))
+  }
+}
+
+/// The task rate wins, then the rate of the first task tag having one, then
+/// the project rate.
+pub fn resolve_rate(task: &Task, tags: &[Tag], project: Option<&Project>) -> Option<Rate> {
+  if let Some(rate) = task.rate() {
+    return Some(rate.clone());
+  }
+
+  let tag_rate = task.tags().iter().find_map(|tag_id| {
+    tags
+      .iter()
+      .find(|tag| tag.id() == *tag_id)
+      .and_then(|tag| tag.rate().cloned())
+  });
+  if tag_rate.is_some() {
+    return tag_rate;
+  }
+
+  return project.and_then(|project| project.rate().cloned());
+}
+
+/// A task is billable unless it or its project says otherwise.
+pub fn is_billable(task: &Task, project: Option<&Project>) -> bool {
+  match task.billable() {
+    Some(billable) => billable,
+    None => project.map_or(true, |project| project.is_billable()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{is_billable, resolve_rate, Money, Rate};
+  use crate::{project::Project, tag::Tag, task::Task, traits::Indexable};
+
+  fn from_json<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> T {
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn test_rate_precedence() {
+    let project: Project = from_json(serde_json::json!({
+      "id": uuid::Uuid::new_v4(), "name": "client",
+      "rate": {"per_hour": 50.0, "currency": "EUR"}
+    }));
+    let plain_tag = Tag::new("plain");
+    let rated_tag: Tag = from_json(serde_json::json!({
+      "id": uuid::Uuid::new_v4(), "name": "senior",
+      "rate": {"per_hour": 80.0, "currency": "EUR"}
+    }));
+    let tags = vec![plain_tag.clone(), rated_tag.clone()];
+
+    let mut task = Task::new(project.id(), "work", vec![plain_tag.id()], None, None);
+    assert_eq!(resolve_rate(&task, &tags, Some(&project)).unwrap().per_hour, 50.0);
+
+    task = Task::new(project.id(), "work", vec![plain_tag.id(), rated_tag.id()], None, None);
+    assert_eq!(resolve_rate(&task, &tags, Some(&project)).unwrap().per_hour, 80.0);
+
+    let mut task_json = serde_json::to_value(&task).unwrap();
+    task_json["rate"] = serde_json::json!({"per_hour": 100.0, "currency": "USD"});
+    task = from_json(task_json);
+    assert_eq!(
+      resolve_rate(&task, &tags, Some(&project)),
+      Some(Rate {
+        per_hour: 100.0,
+        currency: "USD".to_string()
+      })
+    );
+    assert!(is_billable(&task, Some(&project)));
+  }
+
+  #[test]
+  fn test_money_keeps_currencies_apart() {
+    let eur = Rate {
+      per_hour: 60.0,
+      currency: "EUR".to_string(),
+    };
+    let usd = Rate {
+      per_hour: 10.0,
+      currency: "USD".to_string(),
+    };
+
+    let mut money = Money::default();
+    money.add(&eur, chrono::Duration::minutes(90));
+    money.add(&usd, chrono::Duration::hours(2));
+    money.add(&eur, chrono::Duration::minutes(30));
+
+    assert_eq!(money.to_string(), "120.00 EUR, 20.00 USD");
+  }
+}
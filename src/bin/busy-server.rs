@@ -0,0 +1,119 @@
+extern crate clap;
+extern crate serde_json;
+extern crate tiny_http;
+
+use std::collections::HashMap;
+
+use busy::sync::{protocol::SyncRequest, server::ServerDatabase};
+use clap::Parser;
+
+/// Sync server for busy databases, used by the `Http` syncer
+#[derive(clap::Parser)]
+struct BusyServerCli {
+  /// Address to listen on
+  #[clap(long, default_value = "127.0.0.1:7878")]
+  listen: String,
+  /// Folder to store databases in
+  #[clap(long, default_value = "busy-server-data")]
+  data_dir: String,
+  /// Require `Authorization: Bearer <token>` on every request
+  #[clap(long)]
+  token: Option<String>,
+}
+
+struct HttpError(u16, String);
+
+fn main() {
+  env_logger::init();
+
+  let cli = BusyServerCli::parse();
+  std::fs::create_dir_all(&cli.data_dir).unwrap();
+
+  let server = tiny_http::Server::http(&cli.listen).unwrap();
+  println!("busy-server is listening on {}", cli.listen);
+
+  let mut databases: HashMap<String, ServerDatabase> = HashMap::new();
+  for mut request in server.incoming_requests() {
+    let result = handle_request(&cli, &mut databases, &mut request);
+    let response = match result {
+      Ok(body) => tiny_http::Response::from_string(body).with_header(
+        "Content-Type: application/json"
+          .parse::<tiny_http::Header>()
+          .unwrap(),
+      ),
+      Err(HttpError(code, message)) => {
+        log::debug!("{} {} failed: {code} {message}", request.method(), request.url());
+        tiny_http::Response::from_string(message).with_status_code(code)
+      }
+    };
+
+    if let Err(err) = request.respond(response) {
+      eprintln!("can't respond: {err}");
+    }
+  }
+}
+
+fn handle_request(
+  cli: &BusyServerCli,
+  databases: &mut HashMap<String, ServerDatabase>,
+  request: &mut tiny_http::Request,
+) -> Result<String, HttpError> {
+  if let Some(token) = cli.token.as_ref() {
+    let authorized = request.headers().iter().any(|header| {
+      header.field.equiv("Authorization") && header.value.as_str() == format!("Bearer {token}")
+    });
+    if !authorized {
+      return Err(HttpError(401, "unauthorized".to_string()));
+    }
+  }
+
+  let database_name = parse_sync_url(request.url())
+    .ok_or(HttpError(404, format!("unknown path: {}", request.url())))?;
+  if *request.method() != tiny_http::Method::Post {
+    return Err(HttpError(405, "only POST is supported".to_string()));
+  }
+
+  let mut body = String::new();
+  request
+    .as_reader()
+    .read_to_string(&mut body)
+    .map_err(|err| HttpError(400, err.to_string()))?;
+  let sync_request: SyncRequest =
+    serde_json::from_str(&body).map_err(|err| HttpError(400, err.to_string()))?;
+
+  let filepath = std::path::Path::new(&cli.data_dir).join(format!("{database_name}.json"));
+  if !databases.contains_key(&database_name) {
+    let database =
+      ServerDatabase::restore(&filepath).map_err(|err| HttpError(500, err.to_string()))?;
+    databases.insert(database_name.clone(), database);
+  }
+
+  let database = databases.get_mut(&database_name).unwrap();
+  let response = database.sync(&sync_request);
+  if !sync_request.dry_run {
+    database
+      .save(&filepath)
+      .map_err(|err| HttpError(500, err.to_string()))?;
+  }
+
+  log::debug!(
+    "database: {database_name} device: {} sent: {} received: {}",
+    sync_request.device_id,
+    sync_request.changes.len(),
+    response.changes.len()
+  );
+  return serde_json::to_string(&response).map_err(|err| HttpError(500, err.to_string()));
+}
+
+/// Extracts the database name from `/databases/<name>/sync`.
+fn parse_sync_url(url: &str) -> Option<String> {
+  let name = url.strip_prefix("/databases/")?.strip_suffix("/sync")?;
+  let is_valid = !name.is_empty()
+    && name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  match is_valid {
+    true => Some(name.to_owned()),
+    false => None,
+  }
+}
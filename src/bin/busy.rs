@@ -17,13 +17,19 @@ use std::{
 };
 
 use busy::{
-  duration::{get_midnight_datetime, get_period_since_now, get_week_start_datetime, Period},
+  duration::{
+    get_midnight_datetime, get_period_since_now, get_week_start_datetime, parse_duration, Calendar,
+    Period,
+  },
+  fields::{parse_fields, split_field},
+  recurring::Occurrence,
+  template::{resolve_task_input, TaskTemplate, TEMPLATE_PREFIX},
   Busy,
 };
 
 use busy::task::Task;
-use busy::task::TaskView;
-use busy::time::parse_datetime;
+use busy::task::{TaskDetails, TaskView};
+use busy::time::{parse_date, parse_datetime, DisplayZone};
 use busy::traits::Indexable;
 use clap::{CommandFactory, Parser};
 use colored::Colorize;
@@ -41,68 +47,182 @@ fn main() {
   let cli = BusyCli::parse();
 
   let busy = Rc::new(RefCell::new(Busy::new()));
-  let viewer = Viewer::new(Rc::clone(&busy));
+  let mut viewer = Viewer::new(Rc::clone(&busy));
+  let log_params = match &cli.command {
+    commands::Commands::Log(params) => Some(&params.log_params),
+    commands::Commands::Today(params) => Some(&params.log_params),
+    commands::Commands::Stat(params) => Some(&params.log_params),
+    _ => None,
+  };
+  if log_params.is_some_and(|params| params.recorded_zone) {
+    viewer.set_display_zone(DisplayZone::Recorded);
+  }
+  if let Some(params) = log_params {
+    let field_filters: Result<Vec<(String, String)>, String> =
+      params.fields.iter().map(|field| split_field(field)).collect();
+    match field_filters {
+      Ok(field_filters) => viewer.set_field_filters(field_filters),
+      Err(err) => {
+        eprintln!("Wrong field filter: {err}");
+        return;
+      }
+    }
+  }
+
+  if changes_tasks(&cli.command) {
+    let (added, errors) = busy.borrow_mut().add_recurring_entries();
+    for task in added {
+      println!("Recurring entry added:");
+      viewer.log_task(&task, false);
+    }
+    for err in errors {
+      eprintln!("recurring entry err: {err}");
+    }
+  }
 
-  match &cli.command {
+  run_command(&cli.command, Rc::clone(&busy), &viewer);
+
+  if !matches!(
+    cli.command,
+    commands::Commands::Complete(_) | commands::Commands::Sync(_)
+  ) {
+    spawn_auto_sync(busy);
+  }
+}
+
+/// Commands changing tasks. Recurring entries are added only before them, so
+/// reading commands and the background sync never write.
+fn changes_tasks(command: &commands::Commands) -> bool {
+  return matches!(
+    command,
+    commands::Commands::Start(_)
+      | commands::Commands::Stop(_)
+      | commands::Commands::Pause(_)
+      | commands::Commands::Resume(_)
+      | commands::Commands::Continue(_)
+      | commands::Commands::Add(_)
+      | commands::Commands::Remove(_)
+      | commands::Commands::Edit(_)
+      | commands::Commands::Note(_)
+  );
+}
+
+fn run_command(command: &commands::Commands, busy: Rc<RefCell<Busy>>, viewer: &Viewer) {
+  match command {
     commands::Commands::Start(params) => {
       let mut start_time = None;
+      if let Some(ago) = params.ago.as_ref() {
+        match parse_duration(ago) {
+          Ok(duration) => start_time = Some(chrono::Local::now() - duration),
+          Err(err) => {
+            eprintln!("Can't parse ago parameter: {err}");
+            return;
+          }
+        }
+      }
       if let Some(start_time_str) = params.start_time.as_ref() {
-        let parsed_start_time = parse_datetime(start_time_str);
-        if parsed_start_time.is_err() {
-          eprintln!(
-            "Can't parse start-time parameter: {start_time_str}, err: {:?}",
-            parsed_start_time.err()
-          );
-          return;
+        match parse_datetime(start_time_str) {
+          Ok(parsed_start_time) => start_time = Some(parsed_start_time),
+          Err(err) => {
+            eprintln!("Can't parse start-time parameter: {err}");
+            return;
+          }
         }
-        start_time = Some(parsed_start_time.unwrap());
       }
 
+      let input = match resolve_task_input(
+        busy.borrow().templates(),
+        &params.project_name,
+        params.task_title.as_ref(),
+        &params.tags,
+      ) {
+        Ok(input) => input,
+        Err(err) => {
+          eprintln!("start task err: {err}");
+          return;
+        }
+      };
+
+      let mut details = match parse_task_details(params.estimate.as_ref(), &params.fields) {
+        Ok(details) => details,
+        Err(err) => {
+          eprintln!("Can't parse task details: {err}");
+          return;
+        }
+      };
+      details.fields = input.fields.into_iter().chain(details.fields).collect();
+
       let started_task_result = {
         busy.borrow_mut().start(
-          &params.project_name,
-          &params.task_title,
-          params.tags.clone(),
+          &input.project_name,
+          &input.title,
+          input.tags.into_iter().chain(input.extra).collect(),
           start_time,
+          details,
         )
       };
       match started_task_result {
         Ok(task) => {
           println!("Task started:");
           viewer.log_task(&task, true);
+          viewer.warn_exceeded_budgets(task.project_id());
         }
         Err(err) => eprintln!("start task err: {err}"),
       };
     }
 
-    commands::Commands::Stop => {
-      let stopped_task_result = { busy.borrow_mut().stop() };
-      match stopped_task_result {
-        Ok(task) => {
-          println!("Task stopped:");
-          viewer.log_task(&task, true);
+    commands::Commands::Stop(params) => {
+      let stopped_tasks_result = match resolve_active_task_id(Rc::clone(&busy), params) {
+        Ok(task_id) => busy.borrow_mut().stop(task_id),
+        Err(err) => Err(err),
+      };
+      match stopped_tasks_result {
+        Ok(tasks) => {
+          match tasks.len() {
+            1 => println!("Task stopped:"),
+            _ => println!("Tasks stopped:"),
+          };
+          for task in tasks.iter() {
+            viewer.log_task(task, true);
+          }
         }
         Err(err) => eprintln!("couldn't stop: {err}"),
       };
     }
 
-    commands::Commands::Pause => {
-      let paused_task_result = { busy.borrow_mut().pause() };
-      match paused_task_result {
-        Ok(task) => {
-          println!("Task paused:");
-          viewer.log_task(&task, true);
+    commands::Commands::Pause(params) => {
+      let paused_tasks_result = match resolve_active_task_id(Rc::clone(&busy), params) {
+        Ok(task_id) => busy.borrow_mut().pause(task_id),
+        Err(err) => Err(err),
+      };
+      match paused_tasks_result {
+        Ok(tasks) => {
+          match tasks.len() {
+            1 => println!("Task paused:"),
+            _ => println!("Tasks paused:"),
+          };
+          for task in tasks.iter() {
+            viewer.log_task(task, true);
+          }
         }
         Err(err) => eprintln!("couldn't pause: {err}"),
       };
     }
 
-    commands::Commands::Resume => {
-      let unpaused_task_result = { busy.borrow_mut().resume() };
-      match unpaused_task_result {
-        Ok(task) => {
-          println!("Task resumed:");
-          viewer.log_task(&task, true);
+    commands::Commands::Resume(params) => {
+      let unpaused_tasks_result = match resolve_active_task_id(Rc::clone(&busy), params) {
+        Ok(task_id) => busy.borrow_mut().resume(task_id),
+        Err(err) => Err(err),
+      };
+      match unpaused_tasks_result {
+        Ok(tasks) => {
+          match tasks.len() {
+            1 => println!("Task resumed:"),
+            _ => println!("Tasks resumed:"),
+          };
+          for task in tasks.iter() {
+            viewer.log_task(task, true);
+          }
         }
         Err(err) => println!("couldn't resume: {err}"),
       };
@@ -126,6 +246,7 @@ fn main() {
 
       println!("Continue task:");
       viewer.log_task(task.as_ref().unwrap(), true);
+      viewer.warn_exceeded_budgets(task.unwrap().project_id());
     }
 
     commands::Commands::Tags => {
@@ -134,32 +255,61 @@ fn main() {
       viewer.print_tags();
     }
 
-    commands::Commands::Projects => {
+    commands::Commands::Projects(params) => {
       clear_screen();
       println!("{}", "Projects: ".bright_cyan());
-      viewer.print_projects();
+      viewer.print_projects(params.all);
     }
 
     commands::Commands::Add(params) => {
-      let start_time = parse_datetime(&params.start_time);
-      let finish_time = parse_datetime(&params.finish_time);
+      let input = match resolve_task_input(
+        busy.borrow().templates(),
+        &params.project_name,
+        params.task_title.as_ref(),
+        &params.tags,
+      ) {
+        Ok(input) => input,
+        Err(err) => {
+          eprintln!("add task err: {err}");
+          return;
+        }
+      };
+      let (extra_start_time, extra_finish_time) = match input.add_times() {
+        Ok(times) => times,
+        Err(err) => {
+          eprintln!("add task err: {err}");
+          return;
+        }
+      };
+      let start_time = params.start_time.as_ref().or(extra_start_time);
+      let finish_time = params.finish_time.as_ref().or(extra_finish_time);
 
-      if start_time.is_err() || finish_time.is_err() {
-        eprintln!(
-          "failed to parse start or finish time: {:?} {:?}",
-          start_time.err(),
-          finish_time.err()
-        );
-        return;
-      }
+      let (start_time, finish_time) =
+        match resolve_add_times(start_time, finish_time, params.duration.as_ref()) {
+          Ok(times) => times,
+          Err(err) => {
+            eprintln!("failed to resolve start or finish time: {err}");
+            return;
+          }
+        };
+
+      let mut details = match parse_task_details(params.estimate.as_ref(), &params.fields) {
+        Ok(details) => details,
+        Err(err) => {
+          eprintln!("Can't parse task details: {err}");
+          return;
+        }
+      };
+      details.fields = input.fields.into_iter().chain(details.fields).collect();
 
       let started_task_result = {
         busy.borrow_mut().add(
-          &params.project_name,
-          &params.task_title,
-          params.tags.clone(),
-          start_time.unwrap(),
-          finish_time.unwrap(),
+          &input.project_name,
+          &input.title,
+          input.tags,
+          start_time,
+          finish_time,
+          details,
         )
       };
 
@@ -193,7 +343,7 @@ fn main() {
       if params.all_tags {
         edit(
           Rc::clone(&busy),
-          &viewer,
+          viewer,
           EditDataType::AllTags,
           uuid::Uuid::new_v4(),
         );
@@ -203,7 +353,7 @@ fn main() {
       if params.all {
         edit(
           Rc::clone(&busy),
-          &viewer,
+          viewer,
           EditDataType::AllTasks,
           uuid::Uuid::new_v4(),
         );
@@ -222,7 +372,7 @@ fn main() {
           .collect();
 
         for id in ids {
-          edit(Rc::clone(&busy), &viewer, edit_type, id);
+          edit(Rc::clone(&busy), viewer, edit_type, id);
         }
       };
 
@@ -233,37 +383,168 @@ fn main() {
       println!("\nEdit completed");
     }
 
-    commands::Commands::Status => {
-      match busy.borrow().active_task() {
-        Some(task) => {
-          println!("Your active task:");
+    commands::Commands::Show(params) => {
+      let id = restore_id_by_short_id(Rc::clone(&busy), &params.short_id);
+      if id.is_err() {
+        eprintln!("Parse short id into uuid error: {:?}", id.err());
+        return;
+      }
+      let id = id.unwrap();
+
+      let task = busy.borrow().task_by_id(id);
+      let project = busy.borrow().project_by_id(id);
+      let tag = busy.borrow().tag_by_id(id);
+      if let Some(task) = task {
+        viewer.show_task(&task);
+      } else if let Some(project) = project {
+        viewer.show_project(&project);
+      } else if let Some(tag) = tag {
+        viewer.show_tag(&tag);
+      }
+    }
+
+    commands::Commands::History(params) => {
+      // Removed tasks can't be resolved by a short id anymore.
+      let task_id = match uuid::Uuid::parse_str(&params.short_task_id) {
+        Ok(id) => Ok(id),
+        Err(_) => restore_id_by_short_id(Rc::clone(&busy), &params.short_task_id),
+      };
+      if task_id.is_err() {
+        eprintln!("Parse short id into uuid error: {:?}", task_id.err());
+        return;
+      }
+
+      let history = busy.borrow_mut().task_history(task_id.unwrap());
+      match history {
+        Ok(entries) => viewer.print_task_history(&entries),
+        Err(err) => eprintln!("can't read the task history: {err}"),
+      }
+    }
+
+    commands::Commands::Note(params) => {
+      let (short_task_id, text) = match params.args.as_slice() {
+        [short_task_id, text] => (Some(short_task_id), text),
+        [text] => (None, text),
+        _ => unreachable!("clap accepts one or two values"),
+      };
+      let mut task_id = None;
+      if let Some(short_task_id) = short_task_id {
+        match restore_id_by_short_id(Rc::clone(&busy), short_task_id) {
+          Ok(id) => task_id = Some(id),
+          Err(err) => {
+            eprintln!("Parse short id into uuid error: {err}");
+            return;
+          }
+        }
+      }
+
+      let note_result = busy.borrow_mut().add_note(task_id, text);
+      match note_result {
+        Ok(task) => {
+          println!("Note added:");
           viewer.log_task(&task, true);
         }
-        None => {
-          eprintln!("There are no active tasks");
+        Err(err) => eprintln!("add note err: {err}"),
+      };
+    }
+
+    commands::Commands::Balance(params) => {
+      let period = get_period(&params.period, &busy.borrow().calendar());
+      match period {
+        Ok(period) => viewer.show_balance(period),
+        Err(err) => eprintln!("Wrong period: {err}"),
+      }
+    }
+
+    commands::Commands::Template(params) => match &params.command {
+      commands::TemplateCommands::Add(template_params) => {
+        let fields = match parse_fields(&template_params.fields) {
+          Ok(fields) => fields,
+          Err(err) => {
+            eprintln!("Can't parse template fields: {err}");
+            return;
+          }
+        };
+        let template = TaskTemplate {
+          project: template_params.project_name.clone(),
+          title: template_params.task_title.clone(),
+          tags: template_params.tags.clone(),
+          fields,
+        };
+        let name = template_params.name.trim_start_matches(TEMPLATE_PREFIX);
+        match busy.borrow_mut().save_template(name, template) {
+          Ok(_) => println!("Template {TEMPLATE_PREFIX}{name} saved"),
+          Err(err) => eprintln!("save template err: {err}"),
+        }
+      }
+      commands::TemplateCommands::List => viewer.print_templates(),
+      commands::TemplateCommands::Rm(template_params) => {
+        let name = template_params.name.trim_start_matches(TEMPLATE_PREFIX);
+        match busy.borrow_mut().remove_template(name) {
+          Ok(_) => println!("Template {TEMPLATE_PREFIX}{name} removed"),
+          Err(err) => eprintln!("remove template err: {err}"),
         }
+      }
+    },
+
+    commands::Commands::Status => {
+      let active_tasks = busy.borrow().active_tasks();
+      match active_tasks.len() {
+        0 => eprintln!("There are no active tasks"),
+        1 => println!("Your active task:"),
+        _ => println!("Your active tasks:"),
       };
+      for task in active_tasks.iter() {
+        viewer.log_task(task, true);
+      }
+      if !active_tasks.is_empty() {
+        viewer.print_budgets(active_tasks.iter().map(|task| task.project_id()).collect());
+      }
+      viewer.print_today_target();
+
+      let pending_changes = busy.borrow().pending_changes();
+      if pending_changes > 0 {
+        println!(
+          "{}",
+          format!("{pending_changes} changes not synced, run `busy sync`").bright_black()
+        );
+      }
     }
 
     commands::Commands::Log(params) => {
-      show_tasks(
-        &params.log_params,
-        Rc::clone(&busy),
-        &viewer,
-        get_period(params.days, params.today),
-      );
+      let period = get_period(&params.period, &busy.borrow().calendar());
+      match period {
+        Ok(period) => show_tasks(&params.log_params, Rc::clone(&busy), viewer, period),
+        Err(err) => eprintln!("Wrong period: {err}"),
+      }
     }
 
     commands::Commands::Today(params) => {
+      if let Err(err) = resolve_recurring(
+        Rc::clone(&busy),
+        params.confirm.as_ref(),
+        params.dismiss.as_ref(),
+      ) {
+        eprintln!("recurring entry err: {err}");
+        return;
+      }
+
       show_tasks(
         &params.log_params,
         Rc::clone(&busy),
-        &viewer,
-        Period::new_to_now(get_midnight_datetime()),
+        viewer,
+        Period::new_to_now(get_midnight_datetime(&busy.borrow().calendar())),
       );
+      viewer.print_pending_recurring(&busy.borrow().pending_recurring_entries());
     }
 
     commands::Commands::Stat(params) => {
+      let period = get_period(&params.period, &busy.borrow().calendar());
+      if let Err(err) = period {
+        eprintln!("Wrong period: {err}");
+        return;
+      }
+
       if !params.log_params.dont_clear {
         clear_screen();
       }
@@ -271,16 +552,52 @@ fn main() {
       let project_ids = projects_to_ids_set(Rc::clone(&busy), &params.log_params.project);
       let found_tags = busy.borrow().find_tag_by_names(&params.log_params.tag);
 
+      if params.by_ticket {
+        viewer.show_ticket_stat(
+          period.unwrap(),
+          project_ids,
+          &found_tags,
+          params.split_overlaps,
+        );
+        return;
+      }
+
       viewer.show_stat(
-        get_period(params.days, params.today),
+        period.unwrap(),
         project_ids,
         &found_tags,
         params.with_tags,
+        params.by_field.as_deref(),
+        params.split_overlaps,
       );
     }
 
     commands::Commands::Sync(params) => {
-      if params.push_force {
+      if params.auto {
+        if let Err(err) = busy.borrow_mut().sync() {
+          log::debug!("auto sync failed: {err}");
+        }
+      } else if params.status {
+        let status_result = busy.borrow_mut().sync_status();
+        match status_result {
+          Ok(status) => viewer.print_sync_status(&status),
+          Err(err) => eprintln!("Sync status failed, err output:\n{err}"),
+        };
+      } else if params.dry_run {
+        println!("Fetching remote changes…");
+        let dry_run_result = busy.borrow_mut().sync_dry_run();
+        match dry_run_result {
+          Ok(report) => viewer.print_sync_report(&report),
+          Err(err) => eprintln!("Sync dry run failed, err output:\n{err}"),
+        };
+      } else if let Some(older_than_days) = params.compact {
+        println!("Start compacting history older than {older_than_days} days…");
+        let compact_result = busy.borrow_mut().compact_history(older_than_days);
+        match compact_result {
+          Ok(output) => println!("Compacting finished: {output}"),
+          Err(err) => eprintln!("Compacting failed, err output:\n{err}"),
+        };
+      } else if params.push_force {
         println!("Start sync push force…");
         match busy.borrow_mut().push_force() {
           Ok(_) => println!("Sync push force success!"),
@@ -296,7 +613,10 @@ fn main() {
         println!("Start syncing…");
         let sync_result = busy.borrow_mut().sync();
         match sync_result {
-          Ok(_) => {
+          Ok(report) => {
+            if !report.is_empty() {
+              viewer.print_sync_report(&report);
+            }
             println!("Syncing finished");
           }
           Err(err) => {
@@ -313,6 +633,27 @@ fn main() {
   };
 }
 
+/// Runs `busy sync --auto` as a detached process when the auto sync policy
+/// asks for it, so the command never waits on the network.
+fn spawn_auto_sync(busy: Rc<RefCell<Busy>>) {
+  if !busy.borrow().is_auto_sync_due() {
+    return;
+  }
+  busy.borrow_mut().mark_auto_sync_started();
+
+  let spawned = std::env::current_exe().and_then(|exe| {
+    std::process::Command::new(exe)
+      .args(["sync", "--auto"])
+      .stdin(std::process::Stdio::null())
+      .stdout(std::process::Stdio::null())
+      .stderr(std::process::Stdio::null())
+      .spawn()
+  });
+  if let Err(err) = spawned {
+    eprintln!("can't start auto sync: {err}");
+  }
+}
+
 fn show_tasks(
   params: &commands::LogCommonParams,
   busy: Rc<RefCell<Busy>>,
@@ -329,6 +670,40 @@ fn show_tasks(
   viewer.log_tasks_list(period, project_ids, &found_tags, params.full);
 }
 
+/// Adds or skips the recurring entries offered by `busy today`, selected by
+/// template name or `all`.
+fn resolve_recurring(
+  busy: Rc<RefCell<Busy>>,
+  confirm: Option<&String>,
+  dismiss: Option<&String>,
+) -> Result<(), String> {
+  let pending = busy.borrow().pending_recurring_entries();
+  let select = |name: &String| -> Result<Vec<Occurrence>, String> {
+    let name = name.trim_start_matches(TEMPLATE_PREFIX);
+    let selected: Vec<Occurrence> = pending
+      .iter()
+      .filter(|occurrence| name == "all" || occurrence.entry.template == name)
+      .cloned()
+      .collect();
+    if selected.is_empty() && name != "all" {
+      return Err(format!("'{name}' isn't offered today"));
+    }
+    return Ok(selected);
+  };
+
+  if let Some(name) = confirm {
+    for occurrence in select(name)? {
+      busy.borrow_mut().confirm_recurring_entry(&occurrence)?;
+    }
+  }
+  if let Some(name) = dismiss {
+    for occurrence in select(name)? {
+      busy.borrow_mut().dismiss_recurring_entry(&occurrence);
+    }
+  }
+  return Ok(());
+}
+
 #[derive(Debug, Clone, Copy)]
 enum EditDataType {
   Task,
@@ -440,9 +815,8 @@ fn projects_to_ids_set(
 ) -> Option<HashSet<uuid::Uuid>> {
   let mut project_ids = HashSet::new();
   for project_name in project_names.iter() {
-    let project = busy.borrow().project_by_name(project_name);
-    if project.is_some() {
-      project_ids.insert(project.unwrap().id().clone());
+    for project in busy.borrow().projects_within(project_name) {
+      project_ids.insert(project.id());
     }
   }
   if project_ids.is_empty() {
@@ -451,14 +825,99 @@ fn projects_to_ids_set(
   return Some(project_ids);
 }
 
-fn get_period(days: Option<i64>, today: bool) -> Period {
-  if today {
-    return Period::new_to_now(get_midnight_datetime());
+fn parse_task_details(estimate: Option<&String>, fields: &[String]) -> Result<TaskDetails, String> {
+  return Ok(TaskDetails {
+    estimate: estimate.map(|estimate| parse_duration(estimate)).transpose()?,
+    fields: parse_fields(fields)?,
+  });
+}
+
+fn get_period(params: &commands::PeriodParams, calendar: &Calendar) -> Result<Period, String> {
+  let today = calendar.today();
+  let previous = |period: Period| period.from.date_naive().pred_opt().unwrap_or(today);
+
+  if let Some(from) = params.from.as_ref() {
+    let from = parse_period_bound(from, calendar, false)?;
+    let period = match params.to.as_ref() {
+      Some(to) => Period::new(from, parse_period_bound(to, calendar, true)?),
+      None => Period::new_to_now(from),
+    };
+    if period.to < period.from {
+      return Err("--to is before --from".to_string());
+    }
+    return Ok(period);
   }
+  if let Some(on) = params.on.as_ref() {
+    return Ok(Period::day(parse_date(on, today)?, calendar));
+  }
+  if let Some(n) = params.days {
+    return Ok(Period::new_to_now(get_period_since_now(n, calendar)));
+  }
+
+  let period = match params {
+    _ if params.today => Period::new_to_now(get_midnight_datetime(calendar)),
+    _ if params.yesterday => Period::day(today.pred_opt().unwrap_or(today), calendar),
+    _ if params.week => Period::week(today, calendar),
+    _ if params.last_week => Period::week(previous(Period::week(today, calendar)), calendar),
+    _ if params.month => Period::month(today, calendar),
+    _ if params.last_month => Period::month(previous(Period::month(today, calendar)), calendar),
+    _ if params.year => Period::year(today, calendar),
+    _ => Period::new_to_now(get_week_start_datetime(calendar)),
+  };
+  return Ok(period);
+}
 
-  match days {
-    Some(n) => Period::new_to_now(get_period_since_now(n)),
-    None => Period::new_to_now(get_week_start_datetime()),
+/// A day or a moment. A day starts the period at its beginning, or ends it
+/// at its end when `is_end`.
+fn parse_period_bound(
+  bound: &str,
+  calendar: &Calendar,
+  is_end: bool,
+) -> Result<chrono::DateTime<chrono::Local>, String> {
+  if let Ok(date) = parse_date(bound, calendar.today()) {
+    return Ok(match is_end {
+      true => Period::day(date, calendar).to,
+      false => calendar.start_of_day(date),
+    });
+  }
+  return parse_datetime(bound);
+}
+
+/// Any two of start time, finish time and duration define the task, the
+/// finish time defaults to now when only the duration is given.
+fn resolve_add_times(
+  start_time: Option<&String>,
+  finish_time: Option<&String>,
+  duration: Option<&String>,
+) -> Result<(chrono::DateTime<chrono::Local>, chrono::DateTime<chrono::Local>), String> {
+  let parse_time = |time: Option<&String>| time.map(|time| parse_datetime(time)).transpose();
+  let start_time = parse_time(start_time)?;
+  let finish_time = parse_time(finish_time)?;
+  let duration = duration.map(|duration| parse_duration(duration)).transpose()?;
+
+  match (start_time, finish_time, duration) {
+    (Some(_), Some(_), Some(_)) => {
+      Err("use only two of --start-time, --finish-time and --duration".to_string())
+    }
+    (Some(start_time), Some(finish_time), None) => Ok((start_time, finish_time)),
+    (Some(start_time), None, Some(duration)) => Ok((start_time, start_time + duration)),
+    (None, finish_time, Some(duration)) => {
+      let finish_time = finish_time.unwrap_or(chrono::Local::now());
+      Ok((finish_time - duration, finish_time))
+    }
+    _ => Err("set --start-time and --finish-time, or --duration".to_string()),
+  }
+}
+
+fn resolve_active_task_id(
+  busy: Rc<RefCell<Busy>>,
+  params: &commands::ActiveTask,
+) -> Result<Option<uuid::Uuid>, String> {
+  match params.short_task_id.as_ref() {
+    Some(short_id) => restore_id_by_short_id(busy, short_id)
+      .map(Some)
+      .map_err(|err| err.to_string()),
+    None => Ok(None),
   }
 }
 
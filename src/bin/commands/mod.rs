@@ -1,22 +1,24 @@
+use busy::storage::{JsonStorage, Storage};
 use clap::{Args, Subcommand};
+use clap_complete::dynamic::{ArgValueCompleter, CompletionCandidate};
 
 #[derive(Subcommand)]
 pub enum Commands {
   /// Start a new task
   Start(Start),
-  /// Stop the current task
-  Stop,
-  /// Pause the current task
-  Pause,
-  /// Resume the last task
-  Resume,
+  /// Stop the current task, or all of them when several are active
+  Stop(ActiveTask),
+  /// Pause the current task, or all of them when several are active
+  Pause(ActiveTask),
+  /// Resume the paused task, or all of them when several are paused
+  Resume(ActiveTask),
   /// Continue a stopped task. It will start a new task with the same title and tags
   Continue(Continue),
 
   /// List all tags
   Tags,
-  /// List all projects
-  Projects,
+  /// List projects, archived ones are hidden by default
+  Projects(Projects),
 
   /// Add a new task
   Add(Add),
@@ -25,6 +27,14 @@ pub enum Commands {
   Remove(Remove),
   /// Edit a task
   Edit(Edit),
+  /// Show all details of a task, project or tag
+  Show(Show),
+  /// Show all changes of a task
+  History(History),
+  /// Add a note to the active task or to the given one
+  Note(Note),
+  /// Manage task templates, used as `busy start @name`
+  Template(Template),
 
   /// Show the current tasks status
   Status,
@@ -35,6 +45,8 @@ pub enum Commands {
   Today(Today),
   /// Show the stat
   Stat(Stat),
+  /// Show overtime and undertime against the working schedule
+  Balance(Balance),
 
   /// Sync with remote. To use remote repo you need to set the `BUSY_REMOTE` env variable
   Sync(Sync),
@@ -42,18 +54,37 @@ pub enum Commands {
   Complete(clap_complete::dynamic::CompleteArgs),
 }
 
+#[derive(Debug, Args)]
+pub struct Projects {
+  /// Show archived projects too
+  #[clap(long)]
+  pub all: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct Add {
-  /// Project name
+  /// Project name or @template
+  #[clap(add = ArgValueCompleter::new(complete_project_names))]
   pub project_name: String,
-  /// Task title
-  pub task_title: String,
+  /// Task title. After a @template: start and finish time
+  pub task_title: Option<String>,
   /// Tags
   pub tags: Vec<String>,
-  /// Start time in format: HH:MM or YYYY-mm-dd HH:MM
-  pub start_time: String,
-  /// Finish time in format: HH:MM or YYYY-mm-dd HH:MM
-  pub finish_time: String,
+  /// Start time, e.g. 14:00, "yesterday 14:00", "mon 9:00", "2h ago" or YYYY-mm-dd HH:MM
+  #[clap(short, long, allow_hyphen_values = true)]
+  pub start_time: Option<String>,
+  /// Finish time, e.g. 15:30, now, "-15m" or YYYY-mm-dd HH:MM. Defaults to now with --duration
+  #[clap(short, long, allow_hyphen_values = true)]
+  pub finish_time: Option<String>,
+  /// Task duration, e.g. 1h30m, 45m or 90s. Use it instead of --start-time or --finish-time
+  #[clap(short, long)]
+  pub duration: Option<String>,
+  /// Estimated effort, e.g. 2h or 1h30m
+  #[clap(long)]
+  pub estimate: Option<String>,
+  /// Custom field, e.g. --field ticket=ENG-42. Can be repeated
+  #[clap(long = "field", value_name = "KEY=VALUE")]
+  pub fields: Vec<String>,
 }
 
 #[derive(Debug, Args)]
@@ -62,6 +93,65 @@ pub struct Remove {
   pub short_task_id: String,
 }
 
+#[derive(Debug, Args)]
+pub struct Show {
+  /// Task, project or tag id
+  pub short_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct History {
+  /// Task id, the full one for removed tasks
+  pub short_task_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct Note {
+  /// Optional task id followed by the note text
+  #[clap(num_args = 1..=2, required = true, value_names = ["TASK_ID", "TEXT"])]
+  pub args: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct Template {
+  #[clap(subcommand)]
+  pub command: TemplateCommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateCommands {
+  /// Add a template or replace the one with the same name
+  Add(TemplateAdd),
+  /// List all templates
+  #[clap(alias = "ls")]
+  List,
+  /// Remove a template
+  #[clap(alias = "remove")]
+  Rm(TemplateRm),
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateAdd {
+  /// Template name, without @
+  pub name: String,
+  /// Project name
+  #[clap(add = ArgValueCompleter::new(complete_project_names))]
+  pub project_name: String,
+  /// Task title
+  pub task_title: String,
+  /// Tags
+  pub tags: Vec<String>,
+  /// Custom field, e.g. --field ticket=ENG-42. Can be repeated
+  #[clap(long = "field", value_name = "KEY=VALUE")]
+  pub fields: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct TemplateRm {
+  /// Template name
+  pub name: String,
+}
+
 #[derive(Debug, Args)]
 pub struct Edit {
   #[clap(long)]
@@ -78,15 +168,31 @@ pub struct Edit {
 
 #[derive(Debug, Args)]
 pub struct Start {
-  /// Project name
+  /// Project name or @template
+  #[clap(add = ArgValueCompleter::new(complete_project_names))]
   pub project_name: String,
-  /// Task title
-  pub task_title: String,
+  /// Task title. After a @template: more tags
+  pub task_title: Option<String>,
   /// Tags
   pub tags: Vec<String>,
-  /// Override start time in format: HH:MM or YYYY-mm-dd HH:MM
-  #[clap(short, long)]
+  /// Override start time, e.g. 9:00, "15m ago", -1h30m, "mon 9:00" or YYYY-mm-dd HH:MM
+  #[clap(short, long, allow_hyphen_values = true)]
   pub start_time: Option<String>,
+  /// Start the given duration ago, e.g. 20m or 1h15m
+  #[clap(long, conflicts_with = "start_time")]
+  pub ago: Option<String>,
+  /// Estimated effort, e.g. 2h or 1h30m
+  #[clap(long)]
+  pub estimate: Option<String>,
+  /// Custom field, e.g. --field ticket=ENG-42. Can be repeated
+  #[clap(long = "field", value_name = "KEY=VALUE")]
+  pub fields: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ActiveTask {
+  /// Task id, every active task when omitted
+  pub short_task_id: Option<String>,
 }
 
 #[derive(Debug, Args)]
@@ -98,46 +204,126 @@ pub struct Continue {
 pub struct Today {
   #[clap(flatten)]
   pub log_params: LogCommonParams,
+  /// Add the offered recurring entry, by template name or `all`
+  #[clap(long, value_name = "NAME")]
+  pub confirm: Option<String>,
+  /// Skip the offered recurring entry for today, by template name or `all`
+  #[clap(long, value_name = "NAME", conflicts_with = "confirm")]
+  pub dismiss: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct Log {
-  #[clap(long)]
-  pub days: Option<i64>,
-  #[clap(long)]
-  pub today: bool,
+  #[clap(flatten)]
+  pub period: PeriodParams,
   #[clap(flatten)]
   pub log_params: LogCommonParams,
 }
 
+/// Period to show, since the start of the current week by default.
+#[derive(Debug, Args, Clone)]
+pub struct PeriodParams {
+  /// The last N days and today
+  #[clap(long, group = "period")]
+  pub days: Option<i64>,
+  #[clap(long, group = "period")]
+  pub today: bool,
+  #[clap(long, group = "period")]
+  pub yesterday: bool,
+  /// The whole current week
+  #[clap(long, group = "period")]
+  pub week: bool,
+  #[clap(long, group = "period")]
+  pub last_week: bool,
+  /// The whole current month
+  #[clap(long, group = "period")]
+  pub month: bool,
+  #[clap(long, group = "period")]
+  pub last_month: bool,
+  /// The whole current year
+  #[clap(long, group = "period")]
+  pub year: bool,
+  /// A single day, e.g. 2026-10-01, yesterday or mon
+  #[clap(long, group = "period")]
+  pub on: Option<String>,
+  /// Start of the period, a day (2026-10-01, mon) or a time ("2026-10-01 14:00", "2h ago")
+  #[clap(long, conflicts_with = "period", allow_hyphen_values = true)]
+  pub from: Option<String>,
+  /// End of the period, a day is included entirely. Defaults to now
+  #[clap(long, requires = "from", allow_hyphen_values = true)]
+  pub to: Option<String>,
+}
+
 #[derive(Debug, Args, Clone)]
 pub struct LogCommonParams {
   #[clap(long)]
   pub dont_clear: bool,
-  #[clap(long)]
+  #[clap(long, add = ArgValueCompleter::new(complete_project_names))]
   pub project: Vec<String>,
   #[clap(long)]
   pub tag: Vec<String>,
   #[clap(long)]
   pub full: bool,
+  /// Show times in the zone they were recorded in instead of the current one
+  #[clap(long)]
+  pub recorded_zone: bool,
+  /// Only tasks with the field value, e.g. --field ticket=ENG-42. Can be repeated
+  #[clap(long = "field", value_name = "KEY=VALUE")]
+  pub fields: Vec<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct Stat {
-  #[clap(long)]
-  pub days: Option<i64>,
-  #[clap(long)]
-  pub today: bool,
+  #[clap(flatten)]
+  pub period: PeriodParams,
   #[clap(long)]
   pub with_tags: bool,
+  /// Group durations by the value of a custom field instead of projects
+  #[clap(long, value_name = "KEY", conflicts_with = "with_tags")]
+  pub by_field: Option<String>,
+  /// Sum the time per issue reference found in task titles over the whole period
+  #[clap(long, conflicts_with_all = ["with_tags", "by_field"])]
+  pub by_ticket: bool,
+  /// Divide the time tasks ran at once between them instead of counting it fully for each
+  #[clap(long)]
+  pub split_overlaps: bool,
   #[clap(flatten)]
   pub log_params: LogCommonParams,
 }
 
+#[derive(Debug, Args)]
+pub struct Balance {
+  #[clap(flatten)]
+  pub period: PeriodParams,
+}
+
 #[derive(Debug, Args)]
 pub struct Sync {
+  /// Sync quietly, used for the background auto sync
+  #[clap(long, hide = true)]
+  pub auto: bool,
   #[clap(long)]
   pub push_force: bool,
   #[clap(long)]
   pub pull_force: bool,
+  /// Show ahead/behind counts, last sync time and pending local changes
+  #[clap(long)]
+  pub status: bool,
+  /// Fetch the remote and list the changes a sync would make without syncing
+  #[clap(long)]
+  pub dry_run: bool,
+  /// Sync and squash the history older than DAYS (90 by default) into a single commit
+  #[clap(long, value_name = "DAYS", num_args = 0..=1, default_missing_value = "90")]
+  pub compact: Option<i64>,
+}
+
+/// Names of the projects which aren't archived.
+fn complete_project_names() -> Vec<CompletionCandidate> {
+  let config = busy::Config::new();
+  return JsonStorage::new(&config.storage_dir_path)
+    .projects()
+    .iter()
+    .filter(|project| !project.is_archived())
+    .map(|project| CompletionCandidate::new(project.name()))
+    .collect();
 }
@@ -24,6 +24,18 @@ pub fn format_duration_without_paddings(duration: chrono::Duration) -> String {
   );
 }
 
+/// Duration with a sign, e.g. `+1h 30m` or `-45m`.
+pub fn format_signed_duration(duration: chrono::Duration) -> String {
+  let sign = match duration < chrono::Duration::zero() {
+    true => "-",
+    false => "+",
+  };
+  return format!(
+    "{sign}{}",
+    format_duration_without_paddings(duration.abs()).trim_start()
+  );
+}
+
 fn format_number(number: i64, prefix: &str) -> String {
   match number == 0 {
     true => String::new(),
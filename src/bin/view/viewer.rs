@@ -7,14 +7,21 @@ use std::{
 
 use colored::{Color, ColoredString, Colorize};
 
-use super::fmt::{format_duration, format_duration_without_paddings};
+use super::fmt::{format_duration, format_duration_without_paddings, format_signed_duration};
 use {
+  busy::billing::{Money, Rate},
+  busy::budget::{BudgetPeriod, BudgetUsage},
   busy::duration::Period,
+  busy::history::TaskHistoryEntry,
+  busy::overlap::split_overlaps,
   busy::project::Project,
+  busy::record_meta::RecordMeta,
+  busy::recurring::Occurrence,
+  busy::sync::{ChangeKind, RecordChange, SyncReport, SyncStatus},
   busy::tag::Tag,
   busy::task::{self, Task},
-  busy::time::DateTimeInterval,
-  busy::traits::Indexable,
+  busy::time::{DateTimeInterval, DisplayZone},
+  busy::traits::{Indexable, Trackable},
   busy::Busy,
 };
 
@@ -69,6 +76,8 @@ impl ViewPaddings {
     Self::TIME
   );
   pub const TILL_TIME_FRAME: Padding = sum_pads!(Self::LINE_INDENT, Self::ID, Self::PAD);
+  // `Duration: `
+  pub const FIELD_NAME: Padding = Padding(10);
   pub const TILL_PROJECT: Padding = sum_pads!(
     Self::TILL_TIME_FRAME,
     Self::TIME_FRAME,
@@ -78,6 +87,14 @@ impl ViewPaddings {
   );
 }
 
+/// A project, a level of the project hierarchy or a field value in the stat.
+#[derive(Default)]
+struct StatNode {
+  duration: chrono::Duration,
+  money: Money,
+  project_id: Option<uuid::Uuid>,
+}
+
 struct ViewColors {}
 impl ViewColors {
   const ID: Color = Color::BrightBlack;
@@ -97,15 +114,35 @@ impl ViewColors {
 
   const HEADER_DATE: Color = Color::Cyan;
   const HEADER_DURATION: Color = Color::BrightYellow;
+
+  const SYNC_ADDED: Color = Color::Green;
+  const SYNC_CHANGED: Color = Color::Yellow;
+  const SYNC_REMOVED: Color = Color::Red;
 }
 
 pub struct Viewer {
   busy: Rc<RefCell<Busy>>,
+  display_zone: DisplayZone,
+  field_filters: Vec<(String, String)>,
 }
 
 impl Viewer {
   pub fn new(busy: Rc<RefCell<Busy>>) -> Self {
-    Self { busy }
+    let display_zone = busy.borrow().display_zone();
+    Self {
+      busy,
+      display_zone,
+      field_filters: Vec::new(),
+    }
+  }
+
+  pub fn set_display_zone(&mut self, display_zone: DisplayZone) {
+    self.display_zone = display_zone;
+  }
+
+  /// Only tasks having all the given field values are shown.
+  pub fn set_field_filters(&mut self, field_filters: Vec<(String, String)>) {
+    self.field_filters = field_filters;
   }
 
   pub fn print_tags(&self) {
@@ -114,10 +151,65 @@ impl Viewer {
     }
   }
 
-  pub fn print_projects(&self) {
+  pub fn print_projects(&self, show_archived: bool) {
     for project in self.busy.borrow().projects() {
-      self.print_project(&project);
+      if show_archived || !project.is_archived() {
+        self.print_project(&project);
+      }
+    }
+  }
+
+  pub fn print_templates(&self) {
+    let busy = self.busy.borrow();
+    if busy.templates().is_empty() {
+      println!("No templates, add one with `busy template add <name> <project> <title> [tags]`");
+      return;
+    }
+
+    for (name, template) in busy.templates().iter() {
+      let fields: Vec<String> = template
+        .fields
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect();
+      println!(
+        "{pad}{name}{pad}{project}{pad}{title}{pad}{tags}{pad}{fields}",
+        pad = ViewPaddings::PAD,
+        name = format!("@{name}").bold(),
+        project = template.project.color(ViewColors::TASK_PROJECT_NAME),
+        title = template.title,
+        tags = template.tags.join(", ").color(ViewColors::TASK_TAG).italic(),
+        fields = fields.join(" ").bright_black()
+      );
+    }
+  }
+
+  /// Recurring entries waiting for `busy today --confirm` or `--dismiss`.
+  pub fn print_pending_recurring(&self, pending: &[Occurrence]) {
+    if pending.is_empty() {
+      return;
+    }
+
+    let busy = self.busy.borrow();
+    println!("\nRecurring entries to confirm:");
+    for occurrence in pending {
+      let task = match busy.template(&occurrence.entry.template) {
+        Ok(template) => format!(
+          "{} {}",
+          template.project.color(ViewColors::TASK_PROJECT_NAME),
+          template.title
+        ),
+        Err(err) => err.red().to_string(),
+      };
+      println!(
+        "{pad}{name}{pad}{start} - {finish}{pad}{task}",
+        pad = ViewPaddings::PAD,
+        name = format!("@{}", occurrence.entry.template).bold(),
+        start = occurrence.start.format("%H:%M"),
+        finish = occurrence.finish.format("%H:%M"),
+      );
     }
+    println!("Add with `busy today --confirm <name|all>`, skip with `--dismiss <name|all>`");
   }
 
   pub fn print_tag(&self, tag: &Tag) {
@@ -130,20 +222,86 @@ impl Viewer {
   }
 
   pub fn print_project(&self, project: &Project) {
+    let archived = match project.is_archived() {
+      true => " [archived]".bright_black(),
+      false => "".normal(),
+    };
     println!(
-      "{pad}{id}{pad}{project_name}",
+      "{pad}{id}{pad}{project_name}{archived}",
       pad = ViewPaddings::PAD,
       id = self.format_id_with_color(project.id()),
-      project_name = project.name()
+      project_name = project.name().color(project_color(project, ViewColors::TASK_PROJECT_NAME))
     );
   }
 
+  pub fn print_sync_status(&self, status: &SyncStatus) {
+    println!(
+      "Remote: {}",
+      status.remote.as_deref().unwrap_or("not configured").bold()
+    );
+    println!(
+      "Last sync: {}",
+      match status.last_sync {
+        Some(last_sync) => last_sync.format("%Y-%m-%d %H:%M").to_string(),
+        None => "never".to_string(),
+      }
+      .bold()
+    );
+    println!(
+      "Ahead: {ahead}, behind: {behind}",
+      ahead = status.ahead.to_string().bold(),
+      behind = status.behind.to_string().bold()
+    );
+
+    if !status.pending.is_empty() {
+      println!("Pending local changes:");
+      for pending in status.pending.iter() {
+        println!("{indent}{pending}", indent = ViewPaddings::LINE_INDENT);
+      }
+    }
+  }
+
+  pub fn print_sync_report(&self, report: &SyncReport) {
+    if report.is_empty() {
+      println!("nothing to sync");
+      return;
+    }
+    self.print_record_changes("Incoming changes:", &report.incoming);
+    self.print_record_changes("Outgoing changes:", &report.outgoing);
+  }
+
+  fn print_record_changes(&self, header: &str, changes: &[RecordChange]) {
+    if changes.is_empty() {
+      return;
+    }
+
+    println!("{}", header.bright_cyan());
+    for change in changes.iter() {
+      let (sign, color) = match change.kind {
+        ChangeKind::Added => ("+", ViewColors::SYNC_ADDED),
+        ChangeKind::Changed => ("~", ViewColors::SYNC_CHANGED),
+        ChangeKind::Removed => ("-", ViewColors::SYNC_REMOVED),
+      };
+      println!(
+        "{indent}{sign} {collection:8}{pad}{id}{pad}{title}",
+        indent = ViewPaddings::LINE_INDENT,
+        pad = ViewPaddings::PAD,
+        sign = sign.color(color).bold(),
+        collection = change.collection,
+        id = self.format_id_with_color(change.id),
+        title = change.title
+      );
+    }
+  }
+
   pub fn show_stat(
     &self,
     period: Period,
     project_ids: Option<HashSet<uuid::Uuid>>,
     tags: &Vec<Tag>,
     with_tags: bool,
+    by_field: Option<&str>,
+    split_overlapping: bool,
   ) {
     let by_dates = self.tasks_by_day(period, project_ids, tags);
     if by_dates.is_empty() {
@@ -152,66 +310,219 @@ impl Viewer {
     }
 
     let mut total_duration = chrono::Duration::zero();
+    let mut billable_duration = chrono::Duration::zero();
+    let mut total_money = Money::default();
     for tasks in by_dates.iter() {
-      total_duration = total_duration + self.total_time(tasks);
+      let durations = task_durations(tasks, split_overlapping);
+      total_duration = total_duration + durations.values().sum::<chrono::Duration>();
       self.print_header(tasks);
       let mut project_times: BTreeMap<uuid::Uuid, chrono::Duration> = BTreeMap::new();
-      let mut tag_times: HashMap<String, chrono::Duration> = HashMap::new();
+      let mut project_money: HashMap<uuid::Uuid, Money> = HashMap::new();
+      let mut tag_times: HashMap<(uuid::Uuid, String), chrono::Duration> = HashMap::new();
       let mut project_to_tags: HashMap<uuid::Uuid, BTreeSet<String>> = HashMap::new();
+      let mut field_groups: BTreeMap<String, StatNode> = BTreeMap::new();
 
       for task in tasks {
+        let duration = durations[&task.id()];
         let project_id = task.project_id();
+        let mut task_money = Money::default();
         let task_duration = project_times
           .entry(project_id)
           .or_insert(chrono::Duration::zero());
-        *task_duration = task_duration.clone().checked_add(&task.duration()).unwrap();
+        *task_duration = task_duration.clone().checked_add(&duration).unwrap();
+
+        let money = project_money.entry(project_id).or_default();
+        if self.busy.borrow().is_billable(task) {
+          billable_duration = billable_duration + duration;
+          if let Some(rate) = self.busy.borrow().task_rate(task) {
+            money.add(&rate, duration);
+            task_money.add(&rate, duration);
+          }
+        }
+
+        if let Some(key) = by_field {
+          let value = match task.field(key) {
+            Some(value) => value.to_string(),
+            None => "(none)".to_string(),
+          };
+          let group = field_groups.entry(value).or_default();
+          group.duration = group.duration + duration;
+          group.money.merge(&task_money);
+        }
 
         let project_tags = project_to_tags.entry(project_id).or_insert(BTreeSet::new());
         let task_tags = self.busy.borrow().find_tags(task.tags());
 
         for tag in task_tags {
           let tag_duration = tag_times
-            .entry(tag.name().to_string())
+            .entry((project_id, tag.name().to_string()))
             .or_insert(chrono::Duration::zero());
-          *tag_duration = tag_duration.clone().checked_add(&task.duration()).unwrap();
+          *tag_duration = tag_duration.clone().checked_add(&duration).unwrap();
           project_tags.insert(tag.name().to_string());
         }
       }
 
+      if by_field.is_some() {
+        for (value, group) in field_groups.iter() {
+          total_money.merge(&group.money);
+          println!(
+            "{indent}{value}: {duration}{money}",
+            indent = ViewPaddings::LINE_INDENT,
+            value = value.color(ViewColors::STAT_PROJECT),
+            duration = format_duration_without_paddings(group.duration).bold(),
+            money = match group.money.is_empty() {
+              true => String::new(),
+              false => format!("{}{}", ViewPaddings::PAD, group.money),
+            }
+          );
+        }
+        println!();
+        continue;
+      }
+
+      // Durations and amounts are rolled up to every ancestor of a project
+      let mut project_tree: BTreeMap<Vec<String>, StatNode> = BTreeMap::new();
       for (&project_id, &project_time) in project_times.iter() {
+        let money = project_money.remove(&project_id).unwrap_or_default();
+        total_money.merge(&money);
+
+        let path = self.get_project_path(project_id);
+        for depth in 1..=path.len() {
+          let node = project_tree.entry(path[..depth].to_vec()).or_default();
+          node.duration = node.duration + project_time;
+          node.money.merge(&money);
+        }
+        project_tree.get_mut(&path).unwrap().project_id = Some(project_id);
+      }
+
+      for (i, (path, node)) in project_tree.iter().enumerate() {
+        if with_tags && path.len() == 1 && i > 0 {
+          println!();
+        }
+        let indent = format!(
+          "{}{}",
+          ViewPaddings::LINE_INDENT,
+          ViewPaddings::PAD.string().repeat(path.len() - 1)
+        );
+
         let mut tags_str = "".to_string();
-        if with_tags {
+        if let (true, Some(project_id)) = (with_tags, node.project_id) {
           for tag in project_to_tags.entry(project_id).or_default().iter() {
             tags_str += &format!(
               "\n{indent}{pad}+ {tag_name}: {duration}",
-              indent = ViewPaddings::LINE_INDENT,
               pad = ViewPaddings::PAD,
               tag_name = tag.color(ViewColors::STAT_TAG).bold(),
-              duration = format_duration_without_paddings(*tag_times.get(tag).unwrap())
+              duration =
+                format_duration_without_paddings(tag_times[&(project_id, tag.clone())])
             );
           }
-          tags_str += "\n";
         }
 
+        let money_str = match node.money.is_empty() {
+          true => String::new(),
+          false => format!("{}{}", ViewPaddings::PAD, node.money),
+        };
+
         println!(
-          "{indent}{project_name}: {duration}{tags}",
-          indent = ViewPaddings::LINE_INDENT,
-          project_name = self
-            .get_project_name(project_id)
-            .color(ViewColors::STAT_PROJECT),
-          duration = format_duration_without_paddings(project_time).bold(),
+          "{indent}{project_name}: {duration}{money}{tags}",
+          project_name = path.last().unwrap().color(match node.project_id {
+            Some(project_id) => self.get_project_color(project_id, ViewColors::STAT_PROJECT),
+            None => ViewColors::STAT_PROJECT,
+          }),
+          duration = format_duration_without_paddings(node.duration).bold(),
+          money = money_str,
           tags = tags_str
         );
       }
-      if !with_tags {
-        println!("");
-      }
+      println!();
     }
 
     println!(
       "Total: {duration}",
       duration = format_duration_without_paddings(total_duration).bold()
     );
+    let utilisation = match total_duration.num_seconds() {
+      0 => 0.0,
+      total => billable_duration.num_seconds() as f64 * 100.0 / total as f64,
+    };
+    println!(
+      "Billable: {duration} ({utilisation:.0}%)",
+      duration = format_duration_without_paddings(billable_duration).bold()
+    );
+    if !total_money.is_empty() {
+      println!("Amount: {}", total_money.to_string().bold());
+    }
+    self.print_budgets(project_ids_of(&by_dates));
+  }
+
+  /// Time per issue reference over the whole period. A task continued many
+  /// times counts once per reference, a task with several references counts
+  /// toward each of them.
+  pub fn show_ticket_stat(
+    &self,
+    period: Period,
+    project_ids: Option<HashSet<uuid::Uuid>>,
+    tags: &Vec<Tag>,
+    split_overlapping: bool,
+  ) {
+    let by_dates = self.tasks_by_day(period, project_ids, tags);
+    if by_dates.is_empty() {
+      println!("no tasks to show");
+      return;
+    }
+
+    let mut tickets: BTreeMap<String, (chrono::Duration, BTreeSet<String>)> = BTreeMap::new();
+    let mut untracked = chrono::Duration::zero();
+    let mut total = chrono::Duration::zero();
+    for tasks in by_dates.iter() {
+      let durations = task_durations(tasks, split_overlapping);
+      total = total + durations.values().sum::<chrono::Duration>();
+      for task in tasks {
+        let task_duration = durations[&task.id()];
+        let issues = self.busy.borrow().task_issues(task);
+        if issues.is_empty() {
+          untracked = untracked + task_duration;
+          continue;
+        }
+        for issue in issues {
+          let (duration, titles) = tickets
+            .entry(issue)
+            .or_insert((chrono::Duration::zero(), BTreeSet::new()));
+          *duration = *duration + task_duration;
+          titles.insert(task.title().to_owned());
+        }
+      }
+    }
+
+    for (ticket, (duration, titles)) in tickets.iter() {
+      println!(
+        "{indent}{ticket}: {duration}",
+        indent = ViewPaddings::LINE_INDENT,
+        ticket = ticket.color(ViewColors::STAT_PROJECT),
+        duration = format_duration_without_paddings(*duration).bold()
+      );
+      for title in titles {
+        println!(
+          "{indent}{pad}{title}",
+          indent = ViewPaddings::LINE_INDENT,
+          pad = ViewPaddings::PAD,
+          title = title.dimmed().italic()
+        );
+      }
+    }
+    if !untracked.is_zero() {
+      println!(
+        "{indent}{label}: {duration}",
+        indent = ViewPaddings::LINE_INDENT,
+        label = "without ticket".dimmed(),
+        duration = format_duration_without_paddings(untracked).bold()
+      );
+    }
+
+    println!(
+      "\nTotal: {duration}",
+      duration = format_duration_without_paddings(total).bold()
+    );
   }
 
   fn tasks_by_day(
@@ -225,8 +536,8 @@ impl Viewer {
       return Vec::new();
     }
 
-    let mut by_dates: Vec<Vec<Task>> = Vec::new();
-    let mut date = None;
+    let calendar = self.busy.borrow().calendar();
+    let mut by_dates: BTreeMap<chrono::NaiveDate, Vec<Task>> = BTreeMap::new();
     let has_project_ids = maybe_project_ids.is_some();
     let project_ids = maybe_project_ids.unwrap_or_default();
 
@@ -245,14 +556,27 @@ impl Viewer {
         }
       }
 
-      let task_date = task.start_time().date_naive();
-      if date.is_none() || date.unwrap() != task_date {
-        by_dates.push(Vec::new());
-        date = Some(task_date);
+      let has_fields = self.field_filters.iter().all(|(key, value)| {
+        task
+          .field(key)
+          .is_some_and(|field| field.to_string() == *value)
+      });
+      if !has_fields {
+        continue;
+      }
+
+      for (date, day_task) in task.split_by_day(&calendar, self.display_zone) {
+        by_dates.entry(date).or_default().push(day_task);
       }
-      by_dates.last_mut().unwrap().push(task);
     }
-    return by_dates;
+
+    return by_dates
+      .into_values()
+      .map(|mut tasks| {
+        tasks.sort_by_key(|task| task.start_time());
+        tasks
+      })
+      .collect();
   }
 
   pub fn log_tasks_list(
@@ -275,21 +599,28 @@ impl Viewer {
       }
       println!("");
     }
+    self.print_budgets(project_ids_of(&by_dates));
   }
 
+  /// Time on the clock, parallel tasks count once.
   fn total_time(&self, tasks: &Vec<Task>) -> chrono::Duration {
-    return tasks
-      .iter()
-      .map(|t| t.duration())
-      .reduce(|acc, new_d| acc + new_d)
-      .unwrap_or(chrono::Duration::zero());
+    return split_overlaps(tasks).values().sum();
   }
 
   fn print_header(&self, tasks: &Vec<Task>) {
-    let date = tasks.first().unwrap().start_time().date_naive();
+    let date = self
+      .busy
+      .borrow()
+      .calendar()
+      .working_date(&tasks.first().unwrap().start_time_in(self.display_zone));
     let total_time = self.total_time(tasks);
+    let target = self.busy.borrow().schedule().target(date);
+    let remaining = match date == self.busy.borrow().calendar().today() && !target.is_zero() {
+      true => format!(", {}", format_remaining(target, total_time)),
+      false => String::new(),
+    };
     println!(
-      "{date} — {duration}",
+      "{date} — {duration}{remaining}",
       date = date
         .format("%A, %d %B %Y")
         .to_string()
@@ -301,6 +632,76 @@ impl Viewer {
     );
   }
 
+  /// Prints how much is left to work today according to the schedule.
+  pub fn print_today_target(&self) {
+    let today = self.busy.borrow().calendar().today();
+    let calendar = self.busy.borrow().calendar();
+    let balance = self.busy.borrow().balance(Period::day(today, &calendar));
+    let Some(day) = balance.first().filter(|day| !day.target.is_zero()) else {
+      return;
+    };
+    println!(
+      "Today: {worked} of {target}, {remaining}",
+      worked = format_duration_without_paddings(day.worked).bold(),
+      target = format_duration_without_paddings(day.target),
+      remaining = format_remaining(day.target, day.worked)
+    );
+  }
+
+  pub fn show_balance(&self, period: Period) {
+    if self.busy.borrow().schedule().is_empty() {
+      println!("There is no working schedule, set it in the config, e.g. \"schedule\": {{\"Mon\": \"8h\", \"Fri\": \"6h\"}}");
+      return;
+    }
+
+    let balance = self.busy.borrow().balance(period);
+    if balance.is_empty() {
+      println!("no days to show");
+      return;
+    }
+
+    let mut cumulative = chrono::Duration::zero();
+    for day in balance.iter() {
+      cumulative = cumulative + day.difference();
+      println!(
+        "{indent}{date}{pad}{worked:>7} of {target:>7}{pad}{difference:>7}{pad}{cumulative:>8}",
+        indent = ViewPaddings::LINE_INDENT,
+        pad = ViewPaddings::PAD,
+        date = day.date.format("%a, %d %b %Y").to_string().color(ViewColors::HEADER_DATE),
+        worked = format_duration_without_paddings(day.worked),
+        target = format_duration_without_paddings(day.target),
+        difference = format_signed_duration(day.difference()),
+        cumulative = color_balance(cumulative)
+      );
+    }
+
+    let worked = balance.iter().fold(chrono::Duration::zero(), |acc, day| acc + day.worked);
+    let target = balance.iter().fold(chrono::Duration::zero(), |acc, day| acc + day.target);
+    println!(
+      "\nTotal: {worked} of {target}, balance {balance}",
+      worked = format_duration_without_paddings(worked).bold(),
+      target = format_duration_without_paddings(target),
+      balance = color_balance(cumulative).bold()
+    );
+  }
+
+  fn get_project_color(&self, project_id: uuid::Uuid, default: Color) -> Color {
+    match self.busy.borrow().project_by_id(project_id) {
+      Some(project) => project_color(&project, default),
+      None => default,
+    }
+  }
+
+  fn get_project_path(&self, project_id: uuid::Uuid) -> Vec<String> {
+    if let Some(project) = self.busy.borrow().project_by_id(project_id) {
+      let path: Vec<String> = project.path().iter().map(|part| part.to_string()).collect();
+      if !path.is_empty() {
+        return path;
+      }
+    }
+    return vec![self.get_project_name(project_id)];
+  }
+
   fn get_project_name(&self, project_id: uuid::Uuid) -> String {
     if let Some(task_project) = self.busy.borrow().project_by_id(project_id) {
       return task_project.name().to_string();
@@ -316,21 +717,36 @@ impl Viewer {
       .collect();
 
     let project_name = self.get_project_name(task.project_id());
-    let mut project_name_msg = project_name.as_str().color(ViewColors::TASK_PROJECT_NAME);
+    let mut project_name_msg = project_name
+      .as_str()
+      .color(self.get_project_color(task.project_id(), ViewColors::TASK_PROJECT_NAME));
     if task.is_paused() {
       project_name_msg = (project_name + " [paused]").color(ViewColors::TASK_PAUSED_PROJECT_NAME);
     }
 
-    let time_frames = get_formatted_time_intervals(task);
+    // Logs show tasks clipped to the period, the estimate is for the whole task
+    let estimate_usage = self
+      .busy
+      .borrow()
+      .task_by_id(task.id())
+      .unwrap_or(task.clone())
+      .estimate_usage();
+    let estimate = match estimate_usage {
+      Some(usage) => format!("{}{}", ViewPaddings::PAD, format_estimate(&usage)),
+      None => String::new(),
+    };
+
+    let time_frames = get_formatted_time_intervals(task, self.display_zone);
     println!(
-      "{line_indent}{task_id}{pad}{time_frame}{pad}{duration:7}{pad}{project:10}{pad}{tags}",
+      "{line_indent}{task_id}{pad}{time_frame}{pad}{duration:7}{pad}{project:10}{pad}{tags}{estimate}",
       line_indent = ViewPaddings::LINE_INDENT,
       pad = ViewPaddings::PAD,
       task_id = self.format_id_with_color(task.id()),
       time_frame = time_frames.first().unwrap(),
       duration = format_duration(task.duration()),
       project = project_name_msg,
-      tags = tags.join(", ").italic()
+      tags = tags.join(", ").italic(),
+      estimate = estimate
     );
 
     let mut task_description = match show_full {
@@ -354,6 +770,263 @@ impl Viewer {
         description = task_description.take().unwrap_or_default()
       );
     }
+
+    if show_full {
+      for note in task.notes() {
+        println!(
+          "{padding}{time}{pad}{text}",
+          padding = ViewPaddings::TILL_PROJECT,
+          pad = ViewPaddings::PAD,
+          time = note.time.format("%Y-%m-%d %H:%M").to_string().dimmed(),
+          text = note.text.dimmed()
+        );
+      }
+    }
+  }
+
+  /// Prints the budgets of the projects and of the projects they're nested into.
+  pub fn print_budgets(&self, project_ids: HashSet<uuid::Uuid>) {
+    let mut budgets: Vec<(Project, BudgetUsage)> = Vec::new();
+    for project_id in project_ids {
+      for (project, usage) in self.busy.borrow().project_budgets(project_id) {
+        if !budgets.iter().any(|(known, _)| known.id() == project.id()) {
+          budgets.push((project, usage));
+        }
+      }
+    }
+    if budgets.is_empty() {
+      return;
+    }
+
+    budgets.sort_by(|(a, _), (b, _)| a.name().cmp(b.name()));
+    println!("Budgets:");
+    for (project, usage) in budgets.iter() {
+      println!(
+        "{indent}{project_name}: {usage}",
+        indent = ViewPaddings::LINE_INDENT,
+        project_name = project.name().color(ViewColors::STAT_PROJECT),
+        usage = format_budget_usage(usage)
+      );
+    }
+  }
+
+  pub fn warn_exceeded_budgets(&self, project_id: uuid::Uuid) {
+    for (project, usage) in self.busy.borrow().project_budgets(project_id) {
+      if usage.is_exceeded() {
+        eprintln!(
+          "{} project {} is over its budget: {}",
+          "Warning:".yellow().bold(),
+          project.name(),
+          format_budget_usage(&usage)
+        );
+      }
+    }
+  }
+
+  pub fn show_task(&self, task: &Task) {
+    println!("Task {}", self.format_id_with_color(task.id()));
+    print_field("Title", task.title());
+    print_field("Project", &self.get_project_name(task.project_id()));
+
+    let tags: Vec<String> = self
+      .busy
+      .borrow()
+      .find_tags(task.tags())
+      .iter()
+      .map(|tag| tag.name().to_owned())
+      .collect();
+    print_field("Tags", &tags.join(", "));
+
+    for (i, interval) in task.times().iter().enumerate() {
+      print_field(
+        match i {
+          0 => "Times",
+          _ => "",
+        },
+        &format!(
+          "{} to {}",
+          interval
+            .start_in(self.display_zone)
+            .format("%Y-%m-%d %H:%M %:z"),
+          match interval.stop_in(self.display_zone) {
+            Some(stop_time) => stop_time.format("%Y-%m-%d %H:%M %:z").to_string(),
+            None => "now".to_string(),
+          }
+        ),
+      );
+    }
+    print_field("Duration", &format_duration_without_paddings(task.duration()));
+    if task.is_paused() {
+      print_field("Status", "paused");
+    }
+    let billable = self.busy.borrow().is_billable(task);
+    print_field("Billable", if billable { "yes" } else { "no" });
+    if let Some(rate) = self.busy.borrow().task_rate(task) {
+      print_field("Rate", &format_rate(&rate));
+    }
+    if let Some(usage) = task.estimate_usage() {
+      print_field("Estimate", &format_budget_usage(&usage));
+    }
+    let issues = self.busy.borrow().task_issues(task);
+    if !issues.is_empty() {
+      print_field("Issues", &issues.join(", "));
+    }
+    for (key, value) in task.fields().iter() {
+      print_field(key, &value.to_string());
+    }
+    for (i, note) in task.notes().iter().enumerate() {
+      print_field(
+        match i {
+          0 => "Notes",
+          _ => "",
+        },
+        &format!("{} {}", note.time.format("%Y-%m-%d %H:%M"), note.text),
+      );
+    }
+    self.print_record_meta(task.meta());
+  }
+
+  pub fn show_project(&self, project: &Project) {
+    println!("Project {}", self.format_id_with_color(project.id()));
+    print_field("Name", project.name());
+    print_field("Billable", if project.is_billable() { "yes" } else { "no" });
+    if let Some(color) = project.color() {
+      print_field("Color", color);
+    }
+    if !project.default_tags().is_empty() {
+      print_field("Tags", &project.default_tags().join(", "));
+    }
+    if project.is_archived() {
+      print_field("Status", "archived");
+    }
+    if let Some(rate) = project.rate() {
+      print_field("Rate", &format_rate(rate));
+    }
+    if let Some((_, usage)) = self
+      .busy
+      .borrow()
+      .project_budgets(project.id())
+      .into_iter()
+      .find(|(budgeted, _)| budgeted.id() == project.id())
+    {
+      print_field("Budget", &format_budget_usage(&usage));
+    }
+    self.print_record_meta(project.meta());
+  }
+
+  pub fn show_tag(&self, tag: &Tag) {
+    println!("Tag {}", self.format_id_with_color(tag.id()));
+    print_field("Name", tag.name());
+    if let Some(rate) = tag.rate() {
+      print_field("Rate", &format_rate(rate));
+    }
+    self.print_record_meta(tag.meta());
+  }
+
+  pub fn print_task_history(&self, entries: &[TaskHistoryEntry]) {
+    if entries.is_empty() {
+      println!("No history found");
+      return;
+    }
+
+    let this_device_id = self.busy.borrow().device_id();
+    for entry in entries.iter() {
+      let device = match entry.device {
+        Some(device_id) if device_id == this_device_id => " this device".to_string(),
+        Some(device_id) => format!(" device {device_id}"),
+        None => String::new(),
+      };
+      println!(
+        "{time}{pad}{message}{device}",
+        pad = ViewPaddings::PAD,
+        time = entry.time.format("%Y-%m-%d %H:%M:%S").to_string().bright_cyan(),
+        message = entry.message.bold(),
+        device = device.bright_black()
+      );
+
+      for change in entry.changes.iter() {
+        let before = self.format_history_value(&change.field, change.before.as_ref());
+        let after = self.format_history_value(&change.field, change.after.as_ref());
+        print_field(
+          change.field.trim_end_matches("_id"),
+          &format!(
+            "{} -> {}",
+            before.color(ViewColors::SYNC_REMOVED),
+            after.color(ViewColors::SYNC_ADDED)
+          ),
+        );
+      }
+    }
+  }
+
+  /// Shows ids by names and times in the same format as `show`.
+  fn format_history_value(&self, field: &str, value: Option<&serde_json::Value>) -> String {
+    let Some(value) = value else {
+      return "none".to_string();
+    };
+
+    match field {
+      "project_id" => {
+        if let Some(project_id) = value.as_str().and_then(|id| id.parse().ok()) {
+          return self.get_project_name(project_id);
+        }
+      }
+      "tags" => {
+        if let Ok(tag_ids) = serde_json::from_value::<Vec<uuid::Uuid>>(value.clone()) {
+          let tags: Vec<String> = self
+            .busy
+            .borrow()
+            .find_tags(&tag_ids)
+            .iter()
+            .map(|tag| tag.name().to_owned())
+            .collect();
+          return format!("[{}]", tags.join(", "));
+        }
+      }
+      "times" => {
+        if let Ok(times) = serde_json::from_value::<Vec<DateTimeInterval>>(value.clone()) {
+          let times: Vec<String> = times
+            .iter()
+            .map(|interval| {
+              format!(
+                "{} to {}",
+                interval.start_in(self.display_zone).format("%Y-%m-%d %H:%M"),
+                match interval.stop_in(self.display_zone) {
+                  Some(stop_time) => stop_time.format("%Y-%m-%d %H:%M").to_string(),
+                  None => "now".to_string(),
+                }
+              )
+            })
+            .collect();
+          return format!("[{}]", times.join(", "));
+        }
+      }
+      "notes" => {
+        if let Ok(notes) = serde_json::from_value::<Vec<task::TaskNote>>(value.clone()) {
+          let notes: Vec<&str> = notes.iter().map(|note| note.text.as_str()).collect();
+          return format!("[{}]", notes.join(", "));
+        }
+      }
+      _ => {}
+    }
+    return value.to_string();
+  }
+
+  fn print_record_meta(&self, meta: &RecordMeta) {
+    let format_moment = |moment: Option<chrono::DateTime<chrono::Local>>| match moment {
+      Some(moment) => moment.format("%Y-%m-%d %H:%M:%S").to_string(),
+      None => "unknown".to_string(),
+    };
+
+    print_field("Created", &format_moment(meta.created_at));
+    print_field("Updated", &format_moment(meta.updated_at));
+    if let Some(device_id) = meta.updated_by_device {
+      let this_device = match device_id == self.busy.borrow().device_id() {
+        true => " (this device)",
+        false => "",
+      };
+      print_field("Device", &format!("{device_id}{this_device}"));
+    }
   }
 
   fn format_id_with_color(&self, id: uuid::Uuid) -> ColoredString {
@@ -361,7 +1034,94 @@ impl Viewer {
   }
 }
 
-fn get_formatted_time_intervals(task: &Task) -> Vec<String> {
+fn print_field(name: &str, value: &str) {
+  let name = match name.is_empty() {
+    true => String::new(),
+    false => format!("{name}:"),
+  };
+  println!(
+    "{indent}{name:width$}{pad}{value}",
+    indent = ViewPaddings::LINE_INDENT,
+    pad = ViewPaddings::SPACE,
+    name = name.bold(),
+    width = ViewPaddings::FIELD_NAME.size()
+  );
+}
+
+fn project_ids_of(by_dates: &[Vec<Task>]) -> HashSet<uuid::Uuid> {
+  by_dates
+    .iter()
+    .flatten()
+    .map(|task| task.project_id())
+    .collect()
+}
+
+fn format_remaining(target: chrono::Duration, worked: chrono::Duration) -> String {
+  let worked = chrono::Duration::minutes(worked.num_minutes());
+  match worked > target {
+    true => format!("{} over", format_duration_without_paddings(worked - target)),
+    false => format!("{} left", format_duration_without_paddings(target - worked)),
+  }
+}
+
+fn color_balance(balance: chrono::Duration) -> ColoredString {
+  let formatted = format_signed_duration(balance);
+  match balance < chrono::Duration::zero() {
+    true => formatted.red(),
+    false => formatted.green(),
+  }
+}
+
+fn format_estimate(usage: &BudgetUsage) -> ColoredString {
+  let estimate = format!(
+    "[{} of {}]",
+    format_duration_without_paddings(usage.consumed),
+    format_duration_without_paddings(usage.limit)
+  );
+  match usage.is_exceeded() {
+    true => estimate.red(),
+    false => estimate.bright_black(),
+  }
+}
+
+fn format_budget_usage(usage: &BudgetUsage) -> String {
+  // Whole minutes, so the consumed and the remaining time add up to the limit
+  let usage = BudgetUsage {
+    consumed: chrono::Duration::minutes(usage.consumed.num_minutes()),
+    ..*usage
+  };
+  let period = match usage.period {
+    BudgetPeriod::Total => "",
+    BudgetPeriod::Month => " this month",
+  };
+  let rest = match usage.is_exceeded() {
+    true => format!(
+      "exceeded by {}",
+      format_duration_without_paddings(-usage.remaining())
+    )
+    .red(),
+    false => format!("{} left", format_duration_without_paddings(usage.remaining())).normal(),
+  };
+  return format!(
+    "{consumed} of {limit}{period}, {rest}",
+    consumed = format_duration_without_paddings(usage.consumed).bold(),
+    limit = format_duration_without_paddings(usage.limit)
+  );
+}
+
+/// The project colour if it's set and known, `default` otherwise.
+fn project_color(project: &Project, default: Color) -> Color {
+  project
+    .color()
+    .and_then(|color| color.parse().ok())
+    .unwrap_or(default)
+}
+
+fn format_rate(rate: &Rate) -> String {
+  format!("{:.2} {}/h", rate.per_hour, rate.currency)
+}
+
+fn get_formatted_time_intervals(task: &Task, display_zone: DisplayZone) -> Vec<String> {
   let interval_count = task.times().len();
   let mut formatted_time_frames = Vec::new();
   for i in 0..interval_count {
@@ -389,6 +1149,7 @@ fn get_formatted_time_intervals(task: &Task) -> Vec<String> {
     let with_duration = !is_first;
     formatted_time_frames.push(format_time_frame(
       &time_frame,
+      display_zone,
       start_color,
       match time_frame.stop_time.is_some() {
         true => stop_color,
@@ -402,6 +1163,7 @@ fn get_formatted_time_intervals(task: &Task) -> Vec<String> {
 
 fn format_time_frame(
   time_interval: &DateTimeInterval,
+  display_zone: DisplayZone,
   start_time_color: Color,
   stop_time_color: Color,
   with_duration: bool,
@@ -418,14 +1180,25 @@ fn format_time_frame(
 
   format!(
     "{start_time} to {stop_time}{duration}",
-    start_time = format_time(&time_interval.start_time, start_time_color),
+    start_time = format_time(&time_interval.start_in(display_zone), start_time_color),
     stop_time = format_time(
-      &time_interval.stop_time.unwrap_or(chrono::Local::now()),
+      &time_interval
+        .stop_in(display_zone)
+        .unwrap_or(chrono::Local::now().fixed_offset()),
       stop_time_color
     ),
   )
 }
 
-fn format_time(time: &chrono::DateTime<chrono::Local>, color: Color) -> ColoredString {
+/// Time of each task of a day, overlapping time is split between the tasks
+/// when `split_overlapping` is set.
+fn task_durations(tasks: &[Task], split_overlapping: bool) -> HashMap<uuid::Uuid, chrono::Duration> {
+  match split_overlapping {
+    true => split_overlaps(tasks),
+    false => tasks.iter().map(|task| (task.id(), task.duration())).collect(),
+  }
+}
+
+fn format_time(time: &chrono::DateTime<chrono::FixedOffset>, color: Color) -> ColoredString {
   return time.naive_local().format("%H:%M").to_string().color(color);
 }
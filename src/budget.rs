@@ -0,0 +1,80 @@
+use crate::duration::parse_duration;
+
+/// A duration kept in the form accepted by `parse_duration`, e.g. "1h30m", so
+/// it's readable in the edit views.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeAmount(pub chrono::Duration);
+
+impl TryFrom<String> for TimeAmount {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    parse_duration(&value).map(TimeAmount)
+  }
+}
+
+impl From<TimeAmount> for String {
+  fn from(amount: TimeAmount) -> Self {
+    let minutes = amount.0.num_minutes();
+    return match (minutes / 60, minutes % 60) {
+      (0, minutes) => format!("{minutes}m"),
+      (hours, 0) => format!("{hours}h"),
+      (hours, minutes) => format!("{hours}h{minutes}m"),
+    };
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum BudgetPeriod {
+  #[default]
+  Total,
+  /// Counted for the current calendar month only.
+  Month,
+}
+
+/// Time a project may take, its nested projects included.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Budget {
+  pub time: TimeAmount,
+  #[serde(default)]
+  pub period: BudgetPeriod,
+}
+
+/// Consumed time against a task estimate or a project budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetUsage {
+  pub limit: chrono::Duration,
+  pub consumed: chrono::Duration,
+  pub period: BudgetPeriod,
+}
+
+impl BudgetUsage {
+  pub fn remaining(&self) -> chrono::Duration {
+    self.limit - self.consumed
+  }
+
+  pub fn is_exceeded(&self) -> bool {
+    self.consumed > self.limit
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Budget, BudgetPeriod, TimeAmount};
+
+  #[test]
+  fn test_budget_round_trip() {
+    let budget: Budget = serde_json::from_str(r#"{"time": "10h30m", "period": "Month"}"#).unwrap();
+    assert_eq!(budget.time, TimeAmount(chrono::Duration::minutes(630)));
+    assert_eq!(budget.period, BudgetPeriod::Month);
+    assert_eq!(
+      serde_json::to_string(&budget).unwrap(),
+      r#"{"time":"10h30m","period":"Month"}"#
+    );
+
+    let budget: Budget = serde_json::from_str(r#"{"time": "45m"}"#).unwrap();
+    assert_eq!(budget.period, BudgetPeriod::Total);
+    assert!(serde_json::from_str::<Budget>(r#"{"time": "ten hours"}"#).is_err());
+  }
+}
@@ -1,21 +1,46 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use crate::{
-  duration::Period,
+  billing::{self, Rate},
+  budget::{BudgetPeriod, BudgetUsage},
+  duration::{Calendar, Period},
+  fields::FieldValue,
+  history::{build_task_history, ChangeLog, TaskHistoryEntry, TaskRevision},
+  issues::{compile_patterns, extract_issues},
+  overlap::split_overlaps,
   project::Project,
+  recurring::{occurrences, Occurrence, RecurringMode, RecurringState, RECURRING_FIELD},
+  schedule::{daily_balance, DayBalance, Schedule},
   storage::{JsonStorage, Storage},
   sync::Syncer,
-  sync::{EmptySyncer, GitSyncer, SyncerConfig},
+  sync::{
+    AutoSyncPolicy, EmptySyncer, GitSyncer, HttpSyncer, S3Syncer, SyncReport, SyncState, SyncStatus,
+    SyncerConfig,
+  },
   tag::Tag,
-  task::Task,
-  traits::Indexable,
+  task::{Task, TaskDetails},
+  template::{find_template, TaskTemplate},
+  time::DisplayZone,
+  traits::{Indexable, Trackable},
   Config,
 };
 
 pub struct Busy {
   storage: Box<dyn Storage>,
   syncer: Box<dyn Syncer>,
+  sync_state: SyncState,
+  change_log: ChangeLog,
+  recurring_state: RecurringState,
   config: Config,
+  /// Whether this command changed the database, for the `OnMutation` policy.
+  mutated: bool,
+  /// Compiled `issue_patterns`, on the first use only.
+  issue_patterns: std::cell::OnceCell<Vec<regex::Regex>>,
 }
 
+/// How far back missed `Auto` recurring entries are added, e.g. after a vacation.
+const RECURRING_CATCH_UP_DAYS: i64 = 14;
+
 impl Busy {
   pub fn new() -> Self {
     let config = Config::new();
@@ -29,34 +54,133 @@ impl Busy {
         remote,
         remote_branch,
         key_file,
+        batch_commits,
       } => Box::new(GitSyncer::new(
         &config.storage_dir_path,
         key_file,
         Some(remote),
         remote_branch,
+        batch_commits,
+      )),
+      SyncerConfig::S3 {
+        endpoint,
+        bucket,
+        prefix,
+        region,
+        access_key,
+        secret_key,
+      } => Box::new(S3Syncer::new(
+        &config.storage_dir_path,
+        &endpoint,
+        &bucket,
+        prefix,
+        region,
+        &access_key,
+        &secret_key,
+      )),
+      SyncerConfig::Http {
+        url,
+        database,
+        token,
+      } => Box::new(HttpSyncer::new(
+        &config.storage_dir_path,
+        &url,
+        &database,
+        token,
+        config.device_id(),
       )),
     };
 
-    Self {
+    let busy = Self {
       storage: Box::new(JsonStorage::new(&config.storage_dir_path)),
       syncer,
+      sync_state: SyncState::restore(&config.storage_dir_path),
+      change_log: ChangeLog::new(&config.storage_dir_path),
+      recurring_state: RecurringState::restore(&config.storage_dir_path),
       config,
-    }
+      mutated: false,
+      issue_patterns: std::cell::OnceCell::new(),
+    };
+    return busy;
   }
 
-  pub fn sync(&mut self) -> std::io::Result<String> {
-    self.syncer.sync()?;
+  pub fn sync(&mut self) -> std::io::Result<SyncReport> {
+    let result = self.syncer.sync();
+    self.sync_state.record_attempt(result.is_ok());
+    let report = result?;
     self.storage = Box::new(JsonStorage::new(&self.config.storage_dir_path));
 
-    return Ok("sync success".to_string());
+    return Ok(report);
+  }
+
+  pub fn sync_dry_run(&mut self) -> std::io::Result<SyncReport> {
+    self.syncer.dry_run()
+  }
+
+  pub fn sync_status(&mut self) -> std::io::Result<SyncStatus> {
+    let mut status = self.syncer.status()?;
+    status.last_sync = self.sync_state.last_sync;
+    return Ok(status);
   }
 
   pub fn push_force(&mut self) -> std::io::Result<String> {
-    self.syncer.push_force()
+    let result = self.syncer.push_force();
+    self.sync_state.record_attempt(result.is_ok());
+    return result;
   }
 
   pub fn pull_force(&mut self) -> std::io::Result<String> {
-    self.syncer.pull_force()
+    let result = self.syncer.pull_force();
+    self.sync_state.record_attempt(result.is_ok());
+    return result;
+  }
+
+  /// Syncs and squashes the sync history older than `older_than_days`.
+  pub fn compact_history(&mut self, older_than_days: i64) -> std::io::Result<String> {
+    let older_than = chrono::Local::now() - chrono::Duration::days(older_than_days);
+    let result = self.syncer.compact(older_than);
+    self.sync_state.record_attempt(result.is_ok());
+    let output = result?;
+    self.storage = Box::new(JsonStorage::new(&self.config.storage_dir_path));
+
+    return Ok(output);
+  }
+
+  /// Number of changes made since the last successful sync.
+  pub fn pending_changes(&self) -> usize {
+    match self.is_sync_enabled() {
+      true => self.sync_state.pending_changes,
+      false => 0,
+    }
+  }
+
+  pub fn last_sync(&self) -> Option<chrono::DateTime<chrono::Local>> {
+    self.sync_state.last_sync
+  }
+
+  fn is_sync_enabled(&self) -> bool {
+    !matches!(self.config.syncer, SyncerConfig::Empty)
+  }
+
+  /// Whether the `auto_sync` policy asks for a sync after this command. The
+  /// sync itself is started by the caller off the command path.
+  pub fn is_auto_sync_due(&self) -> bool {
+    let should_sync = match self.config.auto_sync {
+      AutoSyncPolicy::Never => false,
+      AutoSyncPolicy::OnMutation => self.mutated,
+      AutoSyncPolicy::Debounced { minutes } => self
+        .sync_state
+        .minutes_since_last_attempt()
+        .map_or(true, |passed| passed >= minutes),
+      AutoSyncPolicy::FirstCommandOfDay => !self.sync_state.attempted_today(),
+    };
+    return should_sync && self.is_sync_enabled();
+  }
+
+  /// Counts the auto sync as attempted right away, so the commands run while
+  /// it's in progress don't start another one.
+  pub fn mark_auto_sync_started(&mut self) {
+    self.sync_state.record_attempt_started();
   }
 
   pub fn shorten_id(&self, id: uuid::Uuid) -> String {
@@ -75,7 +199,8 @@ impl Busy {
           pushed_ids.push(found_tag.id().clone());
         }
         None => {
-          let new_tag = Tag::new(tag);
+          let mut new_tag = Tag::new(tag);
+          self.touch(&mut new_tag);
           self.storage.add_tag(&new_tag);
           pushed_ids.push(new_tag.id().clone());
         }
@@ -91,8 +216,10 @@ impl Busy {
     tags: Vec<String>,
     start_time: chrono::DateTime<chrono::Local>,
     finish_time: chrono::DateTime<chrono::Local>,
+    details: TaskDetails,
   ) -> Result<Task, String> {
     let project = self.upsert_project(project_name);
+    let tags = with_default_tags(&project, tags);
     let task = Task::new(
       project.id(),
       title,
@@ -100,9 +227,16 @@ impl Busy {
       Some(start_time),
       Some(finish_time),
     );
+    return self.add_task(task, details);
+  }
+
+  fn add_task(&mut self, mut task: Task, details: TaskDetails) -> Result<Task, String> {
+    task.set_details(details);
+    self.detect_issues(&mut task);
+    self.touch(&mut task);
     self.storage.add_task(&task);
 
-    self.commit(&format_task_commit("added", &task));
+    self.commit_task("added", &task);
     return Ok(task);
   }
 
@@ -112,85 +246,105 @@ impl Busy {
     title: &str,
     tags: Vec<String>,
     start_time: Option<chrono::DateTime<chrono::Local>>,
+    details: TaskDetails,
   ) -> Result<Task, String> {
-    if !self.active_task().is_none() {
+    if !self.config.parallel_tasks && self.active_task().is_some() {
       return Err("active task already exists, stop it firstly".to_string());
     }
     let project = self.upsert_project(project_name);
-    let task = Task::new(
+    let tags = with_default_tags(&project, tags);
+    let mut task = Task::new(
       project.id(),
       title,
       self.upsert_tags(tags),
       start_time,
       None,
     );
+    task.set_details(details);
+    self.detect_issues(&mut task);
+    self.touch(&mut task);
     self.storage.add_task(&task);
 
-    self.commit(&format_task_commit("started", &task));
+    self.commit_task("started", &task);
 
     return Ok(task);
   }
 
-  pub fn stop(&mut self) -> Result<Task, String> {
-    let maybe_active_task = self.active_task();
-    if maybe_active_task.is_none() {
+  /// Stops the task, or every active task when `task_id` is `None`.
+  pub fn stop(&mut self, task_id: Option<uuid::Uuid>) -> Result<Vec<Task>, String> {
+    let tasks = self.select_active_tasks(task_id, |_| true)?;
+    if tasks.is_empty() {
       return Err("there is no active task to stop".to_owned());
     }
 
-    let mut active_task = maybe_active_task.unwrap();
-    active_task.stop();
-
-    match self.storage.replace_task(&active_task.clone()) {
-      Ok(_) => {
-        self.commit(&format_task_commit("stopped", &active_task));
-        Ok(active_task)
-      }
-      Err(err) => Err(err),
+    let mut stopped = Vec::new();
+    for mut task in tasks {
+      task.stop();
+      self.touch(&mut task);
+      self.storage.replace_task(&task)?;
+      self.commit_task("stopped", &task);
+      stopped.push(task);
     }
+    return Ok(stopped);
   }
 
-  pub fn pause(&mut self) -> Result<Task, String> {
-    let maybe_active_task = self.active_task();
-    if maybe_active_task.is_none() {
+  /// Pauses the task, or every running task when `task_id` is `None`.
+  pub fn pause(&mut self, task_id: Option<uuid::Uuid>) -> Result<Vec<Task>, String> {
+    let tasks = self.select_active_tasks(task_id, |task| !task.is_paused())?;
+    if tasks.is_empty() {
       return Err("there is no active task to pause".to_owned());
     }
 
-    let mut active_task = maybe_active_task.unwrap();
-    active_task.pause();
-
-    match self.storage.replace_task(&active_task) {
-      Ok(_) => {
-        self.commit(&format_task_commit("paused", &active_task));
-        Ok(active_task)
-      }
-      Err(err) => Err(err),
+    let mut paused = Vec::new();
+    for mut task in tasks {
+      task.pause();
+      self.touch(&mut task);
+      self.storage.replace_task(&task)?;
+      self.commit_task("paused", &task);
+      paused.push(task);
     }
+    return Ok(paused);
   }
 
-  pub fn resume(&mut self) -> Result<Task, String> {
-    const ERR_MSG: &str = "there is no paused task to continue";
-
-    let maybe_active_task = self.active_task();
-    if maybe_active_task.is_none() {
-      return Err(ERR_MSG.to_owned());
+  /// Resumes the task, or every paused task when `task_id` is `None`.
+  pub fn resume(&mut self, task_id: Option<uuid::Uuid>) -> Result<Vec<Task>, String> {
+    let tasks = self.select_active_tasks(task_id, |task| task.is_paused())?;
+    if tasks.is_empty() {
+      return Err("there is no paused task to continue".to_owned());
     }
 
-    let mut active_task = maybe_active_task.unwrap();
-    if active_task.stop_time().is_none() {
-      return Err(ERR_MSG.to_owned());
-    }
-    active_task.resume();
-    match self.storage.replace_task(&active_task) {
-      Ok(_) => {
-        self.commit(&format_task_commit("continue", &active_task));
-        Ok(active_task)
-      }
-      Err(err) => Err(err),
+    let mut resumed = Vec::new();
+    for mut task in tasks {
+      task.resume();
+      self.touch(&mut task);
+      self.storage.replace_task(&task)?;
+      self.commit_task("continue", &task);
+      resumed.push(task);
     }
+    return Ok(resumed);
+  }
+
+  /// The active task with the id, or all active tasks, matching the state
+  /// the command expects.
+  fn select_active_tasks(
+    &self,
+    task_id: Option<uuid::Uuid>,
+    in_state: impl Fn(&Task) -> bool,
+  ) -> Result<Vec<Task>, String> {
+    let active_tasks = self.active_tasks();
+    let Some(task_id) = task_id else {
+      return Ok(active_tasks.into_iter().filter(|task| in_state(task)).collect());
+    };
+
+    return match active_tasks.into_iter().find(|task| task.id() == task_id) {
+      Some(task) if in_state(&task) => Ok(vec![task]),
+      Some(_) => Ok(Vec::new()),
+      None => Err(format!("task with id: {task_id} isn't active")),
+    };
   }
 
   pub fn continue_task(&mut self, task_id: uuid::Uuid) -> Result<Task, String> {
-    if self.active_task().is_some() {
+    if !self.config.parallel_tasks && self.active_task().is_some() {
       return Err("found active task, please stop it firstly".to_owned());
     }
 
@@ -199,22 +353,47 @@ impl Busy {
       return Err(format!("task with id: {task_id} not found"));
     }
     let existing_task = maybe_task_to_continue.unwrap();
-    let new_task = Task::new(
+    let mut new_task = Task::new(
       existing_task.project_id(),
       existing_task.title(),
       existing_task.tags().clone(),
       None,
       None,
     );
+    self.detect_issues(&mut new_task);
+    self.touch(&mut new_task);
     self.storage.add_task(&new_task);
-    self.commit(&format_task_commit("continue", &new_task));
+    self.commit_task("continue", &new_task);
     return Ok(new_task);
   }
 
+  /// Appends a note to the task, to the active one when `task_id` is `None`.
+  pub fn add_note(&mut self, task_id: Option<uuid::Uuid>, text: &str) -> Result<Task, String> {
+    let mut task = match task_id {
+      Some(task_id) => self
+        .task_by_id(task_id)
+        .ok_or(format!("task with id: {task_id} not found"))?,
+      None => match self.active_tasks().as_slice() {
+        [] => return Err("there is no active task to add a note to".to_owned()),
+        [task] => task.clone(),
+        _ => return Err("several tasks are active, pass the task id".to_owned()),
+      },
+    };
+    task.add_note(text);
+    self.touch(&mut task);
+
+    self.storage.replace_task(&task)?;
+    self.commit_task("noted", &task);
+    return Ok(task);
+  }
+
   pub fn replace_task(&mut self, task: &Task) -> Result<(), String> {
-    match self.storage.replace_task(task) {
+    let mut task = task.clone();
+    self.detect_issues(&mut task);
+    self.touch(&mut task);
+    match self.storage.replace_task(&task) {
       Ok(_) => {
-        self.commit(&format_task_commit("replace", &task));
+        self.commit_task("replace", &task);
         return Ok(());
       }
       Err(err) => Err(err),
@@ -222,7 +401,9 @@ impl Busy {
   }
 
   pub fn replace_project(&mut self, project: &Project) -> Result<(), String> {
-    match self.storage.replace_project(project) {
+    let mut project = project.clone();
+    self.touch(&mut project);
+    match self.storage.replace_project(&project) {
       Ok(_) => {
         self.commit(&format!(
           "replace task, name: {} id: {}",
@@ -236,22 +417,32 @@ impl Busy {
   }
 
   pub fn remove_task(&mut self, task_id: uuid::Uuid) -> Result<(), String> {
-    self.storage.remove_task(task_id)
+    let task = self
+      .task_by_id(task_id)
+      .ok_or(format!("task with id: {task_id} not found"))?;
+    self.storage.remove_task(task_id)?;
+    self.log_task_change("removed", task_id, None);
+    self.commit(&format_task_commit("removed", &task));
+    return Ok(());
   }
 
+  /// Changes of the task from the oldest to the newest, read from the syncer
+  /// history or from the local change log when the syncer keeps none.
+  pub fn task_history(&mut self, task_id: uuid::Uuid) -> std::io::Result<Vec<TaskHistoryEntry>> {
+    let revisions = match self.syncer.keeps_history() {
+      true => self.syncer.task_history(task_id)?,
+      false => self.change_log.task_revisions(task_id),
+    };
+    return Ok(build_task_history(&revisions));
+  }
+
+  /// Tasks worked on within the period, their intervals are clipped to it.
   pub fn tasks(&self, period: Period) -> Vec<Task> {
     self
       .storage
       .tasks()
       .iter()
-      .filter(|t| {
-        let mut within_the_period = period.contains(&t.start_time());
-        if t.stop_time().is_some() {
-          within_the_period = within_the_period && period.contains(t.stop_time().as_ref().unwrap());
-        }
-        return within_the_period;
-      })
-      .map(|t| t.clone())
+      .filter_map(|t| t.clipped(&period))
       .collect()
   }
 
@@ -259,6 +450,15 @@ impl Busy {
     self.storage.find_tags(tag_ids)
   }
 
+  pub fn task_rate(&self, task: &Task) -> Option<Rate> {
+    let project = self.project_by_id(task.project_id());
+    billing::resolve_rate(task, &self.find_tags(task.tags()), project.as_ref())
+  }
+
+  pub fn is_billable(&self, task: &Task) -> bool {
+    billing::is_billable(task, self.project_by_id(task.project_id()).as_ref())
+  }
+
   pub fn task_by_id(&self, task_id: uuid::Uuid) -> Option<Task> {
     return self
       .storage
@@ -268,15 +468,22 @@ impl Busy {
       .map(|t| t.clone());
   }
 
+  /// The last started of the running or paused tasks.
   pub fn active_task(&self) -> Option<Task> {
-    let tasks = self.storage.tasks();
-    let found_task = tasks
-      .iter()
-      .find(|t| t.stop_time().is_none() || t.is_paused());
-    match found_task {
-      Some(task) => Some(task.clone()),
-      None => None,
-    }
+    self.active_tasks().pop()
+  }
+
+  /// Running and paused tasks, ordered by start time. There's at most one
+  /// unless parallel tasks are enabled.
+  pub fn active_tasks(&self) -> Vec<Task> {
+    let mut tasks: Vec<Task> = self
+      .storage
+      .tasks()
+      .into_iter()
+      .filter(|t| t.stop_time().is_none() || t.is_paused())
+      .collect();
+    tasks.sort_by_key(|task| task.start_time());
+    return tasks;
   }
 
   pub fn projects(&self) -> Vec<Project> {
@@ -301,7 +508,9 @@ impl Busy {
   }
 
   pub fn replace_tag(&mut self, tag: &Tag) -> Result<(), String> {
-    self.storage.replace_tag(tag)
+    let mut tag = tag.clone();
+    self.touch(&mut tag);
+    self.storage.replace_tag(&tag)
   }
 
   pub fn all_tasks(&self) -> Vec<Task> {
@@ -313,17 +522,105 @@ impl Busy {
   }
 
   pub fn replace_tags(&mut self, tags: Vec<Tag>) {
+    let tags = self.touch_changed(&self.storage.tags(), tags);
     self.storage.replace_tags(tags);
     self.commit("Edit all tags");
   }
 
-  pub fn replace_tasks(&mut self, tasks: Vec<Task>) {
+  pub fn replace_tasks(&mut self, mut tasks: Vec<Task>) {
+    let existing = self.storage.tasks();
+    for task in tasks.iter_mut() {
+      let is_title_changed = existing
+        .iter()
+        .find(|t| t.id() == task.id())
+        .map_or(true, |t| t.title() != task.title());
+      if is_title_changed {
+        self.detect_issues(task);
+      }
+    }
+    let tasks = self.touch_changed(&existing, tasks);
+
+    // Mention every changed task, so the task history can find the commit.
+    let mut changes = Vec::new();
+    for task in tasks.iter() {
+      let is_changed = existing
+        .iter()
+        .find(|t| t.id() == task.id())
+        .map_or(true, |t| t.meta() != task.meta());
+      if is_changed {
+        self.log_task_change("replace", task.id(), Some(task));
+        changes.push(format_task_commit("replace", task));
+      }
+    }
+    for task in existing.iter().filter(|t| !tasks.iter().any(|n| n.id() == t.id())) {
+      self.log_task_change("removed", task.id(), None);
+      changes.push(format_task_commit("removed", task));
+    }
+
     self.storage.replace_tasks(tasks);
-    self.commit("Edit all tasks");
+    match changes.is_empty() {
+      true => self.commit("Edit all tasks"),
+      false => self.commit(&format!("Edit all tasks\n\n{}", changes.join("\n"))),
+    }
+  }
+
+  pub fn device_id(&self) -> uuid::Uuid {
+    self.config.device_id()
+  }
+
+  pub fn display_zone(&self) -> DisplayZone {
+    self.config.display_zone
+  }
+
+  pub fn calendar(&self) -> Calendar {
+    self.config.calendar()
+  }
+
+  fn issue_patterns(&self) -> &[regex::Regex] {
+    self
+      .issue_patterns
+      .get_or_init(|| compile_patterns(&self.config.issue_patterns))
+  }
+
+  fn detect_issues(&self, task: &mut Task) {
+    task.set_issues(extract_issues(task.title(), self.issue_patterns()));
+  }
+
+  /// Issue references of the task. Tasks saved before the references were
+  /// detected are looked up with the current patterns.
+  pub fn task_issues(&self, task: &Task) -> Vec<String> {
+    if !task.issues().is_empty() {
+      return task.issues().clone();
+    }
+    return extract_issues(task.title(), self.issue_patterns());
+  }
+
+  fn touch<T: Trackable>(&self, record: &mut T) {
+    record.meta_mut().touch(self.config.device_id());
+  }
+
+  /// Touches only the records which differ from their existing version.
+  fn touch_changed<T>(&self, existing: &[T], mut records: Vec<T>) -> Vec<T>
+  where
+    T: Indexable + Trackable + serde::Serialize,
+  {
+    for record in records.iter_mut() {
+      let is_changed = match existing.iter().find(|r| r.id() == record.id()) {
+        Some(existing_record) => {
+          serde_json::to_value(&*existing_record).ok() != serde_json::to_value(&*record).ok()
+        }
+        None => true,
+      };
+      if is_changed {
+        self.touch(record);
+      }
+    }
+    return records;
   }
 
   fn add_project(&mut self, project_name: &str) -> Project {
-    let project = Project::new(project_name);
+    let mut project = Project::new(project_name);
+    self.touch(&mut project);
     self.storage.add_project(&project);
     return project;
   }
@@ -345,6 +642,222 @@ impl Busy {
     });
   }
 
+  pub fn templates(&self) -> &BTreeMap<String, TaskTemplate> {
+    &self.config.templates
+  }
+
+  pub fn template(&self, name: &str) -> Result<TaskTemplate, String> {
+    find_template(&self.config.templates, name)
+  }
+
+  /// Adds or replaces the template, templates are kept in the config file.
+  pub fn save_template(&mut self, name: &str, template: TaskTemplate) -> Result<(), String> {
+    self.config.templates.insert(name.to_owned(), template);
+    self.config.save().map_err(|err| err.to_string())
+  }
+
+  pub fn remove_template(&mut self, name: &str) -> Result<(), String> {
+    if self.config.templates.remove(name).is_none() {
+      return Err(format!("template '{name}' not found"));
+    }
+    self.config.save().map_err(|err| err.to_string())
+  }
+
+  /// Adds finished tasks for the `Auto` recurring entries which ended since
+  /// the last check. The very first check looks back to the start of today.
+  /// Tasks get ids derived from the occurrence, so entries added already, by
+  /// this device or a synced one, are skipped. Entries which can't be added,
+  /// e.g. because their template is gone, are skipped too and returned as
+  /// errors once per template.
+  pub fn add_recurring_entries(&mut self) -> (Vec<Task>, Vec<String>) {
+    if self.config.recurring.is_empty() {
+      return (Vec::new(), Vec::new());
+    }
+
+    let calendar = self.calendar();
+    let now = chrono::Local::now();
+    let checked_until = self
+      .recurring_state
+      .checked_until
+      .unwrap_or(calendar.start_of_day(calendar.today()))
+      .max(now - chrono::Duration::days(RECURRING_CATCH_UP_DAYS));
+    let due: Vec<Occurrence> = occurrences(
+      &self.config.recurring,
+      &self.config.holidays,
+      checked_until.date_naive(),
+      now.date_naive(),
+    )
+    .into_iter()
+    .filter(|occurrence| occurrence.entry.mode == RecurringMode::Auto)
+    .filter(|occurrence| occurrence.finish > checked_until && occurrence.finish <= now)
+    .filter(|occurrence| !self.has_recurring_task(occurrence))
+    .collect();
+
+    let mut added = Vec::new();
+    let mut errors = Vec::new();
+    let mut failed_templates = BTreeSet::new();
+    for occurrence in due {
+      if failed_templates.contains(&occurrence.entry.template) {
+        continue;
+      }
+      match self.add_recurring_task(&occurrence) {
+        Ok(task) => added.push(task),
+        Err(err) => {
+          errors.push(format!("can't add {}: {err}", occurrence.key()));
+          failed_templates.insert(occurrence.entry.template.clone());
+        }
+      }
+    }
+    self.recurring_state.checked_until = Some(now);
+    self.recurring_state.save();
+    return (added, errors);
+  }
+
+  /// Today's `Confirm` recurring entries which have started and were neither
+  /// added nor dismissed yet.
+  pub fn pending_recurring_entries(&self) -> Vec<Occurrence> {
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    return occurrences(&self.config.recurring, &self.config.holidays, today, today)
+      .into_iter()
+      .filter(|occurrence| occurrence.entry.mode == RecurringMode::Confirm)
+      .filter(|occurrence| occurrence.start <= now)
+      .filter(|occurrence| !self.recurring_state.dismissed.contains(&occurrence.key()))
+      .filter(|occurrence| !self.has_recurring_task(occurrence))
+      .collect();
+  }
+
+  pub fn confirm_recurring_entry(&mut self, occurrence: &Occurrence) -> Result<Task, String> {
+    self.add_recurring_task(occurrence)
+  }
+
+  /// Stops offering the entry for today, it's offered again on its next day.
+  pub fn dismiss_recurring_entry(&mut self, occurrence: &Occurrence) {
+    let today = chrono::Local::now().date_naive().to_string();
+    let state = &mut self.recurring_state;
+    state.dismissed.retain(|key| key.ends_with(&today));
+    state.dismissed.insert(occurrence.key());
+    state.save();
+  }
+
+  fn has_recurring_task(&self, occurrence: &Occurrence) -> bool {
+    let task_id = occurrence.task_id();
+    let name = FieldValue::Text(occurrence.entry.template.clone());
+    return self.storage.tasks().iter().any(|task| {
+      task.id() == task_id
+        || (task.field(RECURRING_FIELD) == Some(&name)
+          && task.start_time().date_naive() == occurrence.date)
+    });
+  }
+
+  fn add_recurring_task(&mut self, occurrence: &Occurrence) -> Result<Task, String> {
+    let template = self.template(&occurrence.entry.template)?;
+    let mut fields = template.fields;
+    fields.insert(
+      RECURRING_FIELD.to_owned(),
+      FieldValue::Text(occurrence.entry.template.clone()),
+    );
+    let project = self.upsert_project(&template.project);
+    let tags = with_default_tags(&project, template.tags);
+    let task = Task::new(
+      project.id(),
+      &template.title,
+      self.upsert_tags(tags),
+      Some(occurrence.start),
+      Some(occurrence.finish),
+    )
+    .with_id(occurrence.task_id());
+    return self.add_task(
+      task,
+      TaskDetails {
+        estimate: None,
+        fields,
+      },
+    );
+  }
+
+  pub fn schedule(&self) -> &Schedule {
+    &self.config.schedule
+  }
+
+  /// Worked time against the schedule for each working day of the period,
+  /// days after today are left out.
+  pub fn balance(&self, period: Period) -> Vec<DayBalance> {
+    let calendar = self.calendar();
+    let mut tasks_by_day: BTreeMap<chrono::NaiveDate, Vec<Task>> = BTreeMap::new();
+    for task in self.tasks(period.clone()) {
+      for (date, part) in task.split_by_day(&calendar, DisplayZone::Current) {
+        tasks_by_day.entry(date).or_default().push(part);
+      }
+    }
+    // Time of parallel tasks counts once.
+    let worked: BTreeMap<chrono::NaiveDate, chrono::Duration> = tasks_by_day
+      .into_iter()
+      .map(|(date, tasks)| (date, split_overlaps(&tasks).values().sum()))
+      .collect();
+
+    let first = calendar.working_date(&period.from);
+    let last = calendar
+      .working_date(&(period.to - chrono::Duration::nanoseconds(1)))
+      .min(calendar.today());
+    return daily_balance(self.schedule(), &worked, first, last);
+  }
+
+  /// Budgets of the project and of the projects it's nested into.
+  pub fn project_budgets(&self, project_id: uuid::Uuid) -> Vec<(Project, BudgetUsage)> {
+    let Some(project) = self.project_by_id(project_id) else {
+      return Vec::new();
+    };
+
+    let mut budgets = Vec::new();
+    for candidate in self.storage.projects() {
+      let Some(budget) = candidate.budget() else {
+        continue;
+      };
+      if !project.is_within(candidate.name()) {
+        continue;
+      }
+
+      let project_ids: Vec<uuid::Uuid> = self
+        .projects_within(candidate.name())
+        .iter()
+        .map(|p| p.id())
+        .collect();
+      let tasks = match budget.period {
+        BudgetPeriod::Total => self.storage.tasks(),
+        BudgetPeriod::Month => {
+          let calendar = self.calendar();
+          self.tasks(Period::month(calendar.today(), &calendar))
+        }
+      };
+      let consumed = tasks
+        .iter()
+        .filter(|task| project_ids.contains(&task.project_id()))
+        .fold(chrono::Duration::zero(), |acc, task| acc + task.duration());
+
+      budgets.push((
+        candidate.clone(),
+        BudgetUsage {
+          limit: budget.time.0,
+          consumed,
+          period: budget.period,
+        },
+      ));
+    }
+    budgets.sort_by_key(|(candidate, _)| candidate.path().len());
+    return budgets;
+  }
+
+  /// The project with the given name and all projects nested into it.
+  pub fn projects_within(&self, project_name: &str) -> Vec<Project> {
+    self
+      .storage
+      .projects()
+      .into_iter()
+      .filter(|p| p.is_within(project_name))
+      .collect()
+  }
+
   pub fn project_by_id(&self, project_id: uuid::Uuid) -> Option<Project> {
     self.storage.projects().iter().find_map(|c| {
       if c.id() == project_id {
@@ -354,11 +867,36 @@ impl Busy {
     })
   }
 
+  fn commit_task(&mut self, prefix: &str, task: &Task) {
+    self.log_task_change(prefix, task.id(), Some(task));
+    self.commit(&format_task_commit(prefix, task));
+  }
+
+  fn log_task_change(&self, message: &str, task_id: uuid::Uuid, task: Option<&Task>) {
+    if self.syncer.keeps_history() {
+      return;
+    }
+    let revision = TaskRevision {
+      time: chrono::Local::now(),
+      task_id,
+      message: message.to_owned(),
+      task: task.and_then(|task| serde_json::to_value(task).ok()),
+    };
+    if let Err(err) = self.change_log.append(&revision) {
+      eprintln!("can't write the task history: {err}");
+    }
+  }
+
   fn commit(&mut self, msg: &str) {
     match self.syncer.commit(msg) {
       Err(err) => println!("commit err: {err} msg: {msg}"),
       _ => {}
     };
+
+    if self.is_sync_enabled() {
+      self.sync_state.record_change();
+      self.mutated = true;
+    }
   }
 }
 
@@ -370,3 +908,125 @@ fn format_task_commit(prefix: &str, task: &Task) -> String {
     task.project_id()
   )
 }
+
+/// Default tags of the project go first, tags given twice are kept once.
+fn with_default_tags(project: &Project, tags: Vec<String>) -> Vec<String> {
+  let mut all_tags = project.default_tags().clone();
+  for tag in tags {
+    if !all_tags.contains(&tag) {
+      all_tags.push(tag);
+    }
+  }
+  return all_tags;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Busy;
+  use crate::{duration::Period, task::TaskDetails, traits::Indexable};
+
+  /// `BUSY_CONFIG` is process wide, tests set it one at a time.
+  static CONFIG_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+  /// A `Busy` on a fresh storage with the given config fields.
+  fn busy_with_config(dir: &std::path::Path, config: serde_json::Value) -> Busy {
+    let _lock = CONFIG_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut config = config;
+    config["storage_dir_path"] = dir.join("db").to_str().unwrap().into();
+    config["syncer"] = "Empty".into();
+    let config_path = dir.join("config.json");
+    std::fs::write(&config_path, config.to_string()).unwrap();
+    std::env::set_var("BUSY_CONFIG", &config_path);
+    return Busy::new();
+  }
+
+  #[test]
+  fn test_add_recurring_entries_is_idempotent() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut busy = busy_with_config(
+      dir.path(),
+      serde_json::json!({
+        "templates": {"standup": {"project": "team", "title": "daily standup"}},
+        "recurring": [{"template": "standup", "rule": "daily", "start": "00:00:00", "finish": "00:00:01", "mode": "Auto"}],
+      }),
+    );
+
+    let (added, errors) = busy.add_recurring_entries();
+    assert_eq!(added.len(), 1);
+    assert!(errors.is_empty());
+    assert_eq!(added[0].id().get_version_num(), 5);
+
+    // Another device, or this one after losing its local state.
+    busy.recurring_state.checked_until = None;
+    assert!(busy.add_recurring_entries().0.is_empty());
+    assert_eq!(busy.all_tasks().len(), 1);
+    assert_eq!(busy.all_tasks()[0].id(), added[0].id());
+  }
+
+  #[test]
+  fn test_broken_recurring_entry_is_reported_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut busy = busy_with_config(
+      dir.path(),
+      serde_json::json!({
+        "recurring": [{"template": "gone", "rule": "daily", "start": "00:00:00", "finish": "00:00:01", "mode": "Auto"}],
+      }),
+    );
+    busy.recurring_state.checked_until = Some(chrono::Local::now() - chrono::Duration::days(3));
+
+    let (added, errors) = busy.add_recurring_entries();
+    assert!(added.is_empty());
+    assert_eq!(errors.len(), 1);
+
+    let (added, errors) = busy.add_recurring_entries();
+    assert!(added.is_empty());
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn test_parallel_tasks_by_id_and_all() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut busy = busy_with_config(dir.path(), serde_json::json!({"parallel_tasks": true}));
+    let meeting = busy.start("team", "meeting", vec![], None, TaskDetails::default()).unwrap();
+    let incident = busy.start("ops", "incident", vec![], None, TaskDetails::default()).unwrap();
+    assert_eq!(busy.active_tasks().len(), 2);
+
+    let paused = busy.pause(Some(meeting.id())).unwrap();
+    assert_eq!(paused.len(), 1);
+    assert!(paused[0].is_paused());
+    assert!(busy.pause(Some(meeting.id())).is_err());
+    let running = busy.select_active_tasks(Some(meeting.id()), |task| !task.is_paused());
+    assert_eq!(running.unwrap().len(), 0);
+
+    // Only the paused task is resumed, the running one is left alone.
+    let resumed = busy.resume(None).unwrap();
+    assert_eq!(resumed.len(), 1);
+    assert_eq!(resumed[0].id(), meeting.id());
+
+    let stopped = busy.stop(Some(incident.id())).unwrap();
+    assert_eq!(stopped.len(), 1);
+    assert!(busy.stop(Some(incident.id())).is_err());
+    assert_eq!(busy.stop(None).unwrap().len(), 1);
+    assert!(busy.active_tasks().is_empty());
+    assert!(busy.stop(None).is_err());
+  }
+
+  #[test]
+  fn test_single_task_mode_and_wall_clock_balance() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut busy = busy_with_config(dir.path(), serde_json::json!({}));
+    busy.start("team", "meeting", vec![], None, TaskDetails::default()).unwrap();
+    assert!(busy.start("ops", "incident", vec![], None, TaskDetails::default()).is_err());
+    busy.stop(None).unwrap();
+
+    let calendar = busy.calendar();
+    let yesterday = calendar.today().pred_opt().unwrap();
+    let at = |hours| calendar.start_of_day(yesterday) + chrono::Duration::hours(hours);
+    let details = TaskDetails::default;
+    busy.add("team", "meeting", vec![], at(10), at(12), details()).unwrap();
+    busy.add("ops", "incident", vec![], at(11), at(13), details()).unwrap();
+
+    let balance = busy.balance(Period::day(yesterday, &calendar));
+    assert_eq!(balance[0].worked, chrono::Duration::hours(3));
+  }
+}
@@ -1,33 +1,70 @@
-use crate::sync::SyncerConfig;
+use std::collections::BTreeMap;
+
+use crate::{
+  duration::Calendar,
+  issues::DEFAULT_ISSUE_PATTERNS,
+  recurring::RecurringEntry,
+  schedule::Schedule,
+  sync::{AutoSyncPolicy, SyncerConfig},
+  template::TaskTemplate,
+  time::DisplayZone,
+};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Config {
   pub storage_dir_path: String,
   pub syncer: SyncerConfig,
+  #[serde(default)]
+  pub auto_sync: AutoSyncPolicy,
+  /// Stable id of this device, stamped on every changed record. Generated on
+  /// the first run.
+  #[serde(default)]
+  pub device_id: Option<uuid::Uuid>,
+  /// Zone to show task times in, `Current` or `Recorded`.
+  #[serde(default)]
+  pub display_zone: DisplayZone,
+  /// First day of the week, e.g. `Mon` or `Sun`.
+  #[serde(default = "default_week_start")]
+  pub week_start: chrono::Weekday,
+  /// Time the working day starts at, e.g. `04:00` counts a session at 01:30
+  /// toward the previous day.
+  #[serde(default)]
+  pub day_starts_at: chrono::NaiveTime,
+  /// Hours to work per weekday, e.g. `{"Mon": "8h", "Fri": "6h"}`.
+  #[serde(default)]
+  pub schedule: Schedule,
+  /// Regexes finding issue references in task titles, e.g. `ENG-1234` or `#42`.
+  #[serde(default = "default_issue_patterns")]
+  pub issue_patterns: Vec<String>,
+  /// Named tasks to start with `busy start @name`.
+  #[serde(default)]
+  pub templates: BTreeMap<String, TaskTemplate>,
+  /// Tasks from templates repeating on a schedule, e.g. a daily standup.
+  #[serde(default)]
+  pub recurring: Vec<RecurringEntry>,
+  /// Days recurring entries are skipped on, e.g. `2026-12-25`.
+  #[serde(default)]
+  pub holidays: Vec<chrono::NaiveDate>,
+  /// Allows several tasks to be active at once, e.g. a meeting and an incident.
+  #[serde(default)]
+  pub parallel_tasks: bool,
+}
+
+fn default_week_start() -> chrono::Weekday {
+  Calendar::default().week_start
+}
+
+fn default_issue_patterns() -> Vec<String> {
+  DEFAULT_ISSUE_PATTERNS.iter().map(|p| p.to_string()).collect()
 }
 
 impl Config {
   pub fn new() -> Self {
     const BUSY_DEFAULT_STORAGE_DIR: &str = ".busy";
-    const BUSY_DEFAULT_CONFIG_PATH: &str = ".config/busy/config.json";
 
     let home_env = std::env::var("HOME").unwrap();
     let home = std::path::Path::new(home_env.as_str());
-
-    let config_file_path = match std::env::var("BUSY_CONFIG") {
-      Ok(file_path) => std::path::Path::new(&file_path).to_path_buf(),
-      Err(_) => home.join(BUSY_DEFAULT_CONFIG_PATH),
-    };
-
-    let get_config_file = || {
-      std::fs::create_dir_all(config_file_path.parent().unwrap()).unwrap();
-      std::fs::File::options()
-        .create(true)
-        .write(true)
-        .read(true)
-        .open(config_file_path.clone())
-        .unwrap()
-    };
+    let config_file_path = Self::file_path();
 
     if !config_file_path.exists() {
       let config = Self {
@@ -37,12 +74,62 @@ impl Config {
           .unwrap()
           .to_owned(),
         syncer: SyncerConfig::Empty,
+        auto_sync: AutoSyncPolicy::default(),
+        device_id: Some(uuid::Uuid::new_v4()),
+        display_zone: DisplayZone::default(),
+        week_start: default_week_start(),
+        day_starts_at: chrono::NaiveTime::default(),
+        schedule: Schedule::default(),
+        issue_patterns: default_issue_patterns(),
+        templates: BTreeMap::new(),
+        recurring: Vec::new(),
+        holidays: Vec::new(),
+        parallel_tasks: false,
       };
 
-      serde_json::to_writer_pretty(get_config_file(), &config).unwrap();
+      config.save().unwrap();
       return config;
     }
 
-    return serde_json::from_reader(get_config_file()).unwrap();
+    let config_file = std::fs::File::open(&config_file_path).unwrap();
+    let mut config: Self = serde_json::from_reader(config_file).unwrap();
+    if config.device_id.is_none() {
+      config.device_id = Some(uuid::Uuid::new_v4());
+      config.save().unwrap();
+    }
+    return config;
+  }
+
+  fn file_path() -> std::path::PathBuf {
+    const BUSY_DEFAULT_CONFIG_PATH: &str = ".config/busy/config.json";
+
+    match std::env::var("BUSY_CONFIG") {
+      Ok(file_path) => std::path::Path::new(&file_path).to_path_buf(),
+      Err(_) => std::path::Path::new(&std::env::var("HOME").unwrap()).join(BUSY_DEFAULT_CONFIG_PATH),
+    }
+  }
+
+  pub fn save(&self) -> std::io::Result<()> {
+    let config_file_path = Self::file_path();
+    if let Some(parent) = config_file_path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    // Written next to the config and renamed over it, so a failed write
+    // never leaves a truncated config behind. A symlinked config is followed.
+    let config_file_path = std::fs::canonicalize(&config_file_path).unwrap_or(config_file_path);
+    let tmp_file_path = config_file_path.with_extension("json.tmp");
+    std::fs::write(&tmp_file_path, serde_json::to_vec_pretty(self)?)?;
+    return std::fs::rename(tmp_file_path, config_file_path);
+  }
+
+  pub fn device_id(&self) -> uuid::Uuid {
+    self.device_id.unwrap_or_default()
+  }
+
+  pub fn calendar(&self) -> Calendar {
+    Calendar {
+      week_start: self.week_start,
+      day_starts_at: self.day_starts_at,
+    }
   }
 }
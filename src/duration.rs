@@ -1,52 +1,366 @@
-use chrono::{Datelike, Timelike};
+use chrono::{Datelike, TimeZone};
 
+use crate::time::{DateTimeInterval, DisplayZone};
+
+/// How time is split into working days and weeks. A working day starts at
+/// `day_starts_at`, so with `04:00` a session at 01:30 belongs to the day
+/// before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calendar {
+  pub week_start: chrono::Weekday,
+  pub day_starts_at: chrono::NaiveTime,
+}
+
+impl Default for Calendar {
+  fn default() -> Self {
+    Self {
+      week_start: chrono::Weekday::Mon,
+      day_starts_at: chrono::NaiveTime::MIN,
+    }
+  }
+}
+
+impl Calendar {
+  /// The working day the moment belongs to.
+  pub fn working_date<Tz: TimeZone>(&self, moment: &chrono::DateTime<Tz>) -> chrono::NaiveDate {
+    let since_midnight = self.day_starts_at - chrono::NaiveTime::MIN;
+    return (moment.naive_local() - since_midnight).date();
+  }
+
+  pub fn today(&self) -> chrono::NaiveDate {
+    return self.working_date(&chrono::Local::now());
+  }
+
+  /// The first moment of the local working day.
+  pub fn start_of_day(&self, date: chrono::NaiveDate) -> chrono::DateTime<chrono::Local> {
+    return start_of_day_in(&chrono::Local, date, self.day_starts_at);
+  }
+
+  /// Splits the interval at working day boundaries of `zone`.
+  pub fn split_by_day(
+    &self,
+    interval: &DateTimeInterval,
+    zone: DisplayZone,
+  ) -> Vec<(chrono::NaiveDate, DateTimeInterval)> {
+    let mut parts = Vec::new();
+    let mut rest = interval.clone();
+    loop {
+      let date = self.working_date(&rest.start_in(zone));
+      let next_date = date.succ_opt().unwrap_or(date);
+      let next_day_start = match zone {
+        DisplayZone::Current => start_of_day_in(&chrono::Local, next_date, self.day_starts_at).to_utc(),
+        DisplayZone::Recorded => {
+          start_of_day_in(&interval.recorded_offset, next_date, self.day_starts_at).to_utc()
+        }
+      };
+
+      let stop_time = rest.stop_time.unwrap_or(chrono::Utc::now());
+      if stop_time <= next_day_start || next_day_start <= rest.start_time {
+        parts.push((date, rest));
+        return parts;
+      }
+      parts.push((
+        date,
+        DateTimeInterval {
+          stop_time: Some(next_day_start),
+          ..rest.clone()
+        },
+      ));
+      rest.start_time = next_day_start;
+    }
+  }
+
+  /// The first working day of the week containing `date`.
+  pub fn week_start_date(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+    let days_since_start = date.weekday().days_since(self.week_start);
+    return date - chrono::Days::new(days_since_start.into());
+  }
+}
+
+/// Half-open time range `[from, to)`. Calendar periods are built from local
+/// dates, so they are correct on days with a DST transition.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Period {
   pub from: chrono::DateTime<chrono::Local>,
   pub to: chrono::DateTime<chrono::Local>,
+  /// The period isn't over yet, running tasks stay running within it.
+  pub open_ended: bool,
 }
 
 impl Period {
+  pub fn new(from: chrono::DateTime<chrono::Local>, to: chrono::DateTime<chrono::Local>) -> Self {
+    return Self {
+      from,
+      to,
+      open_ended: to > chrono::Local::now(),
+    };
+  }
+
   pub fn new_to_now(from: chrono::DateTime<chrono::Local>) -> Self {
     return Self {
       from,
       to: chrono::Local::now(),
+      open_ended: true,
     };
   }
 
+  /// Working days from `first` to `last`, both included.
+  pub fn dates(first: chrono::NaiveDate, last: chrono::NaiveDate, calendar: &Calendar) -> Self {
+    return Self::new(
+      calendar.start_of_day(first),
+      calendar.start_of_day(last.succ_opt().unwrap_or(last)),
+    );
+  }
+
+  pub fn day(date: chrono::NaiveDate, calendar: &Calendar) -> Self {
+    return Self::dates(date, date, calendar);
+  }
+
+  /// The week containing `date`.
+  pub fn week(date: chrono::NaiveDate, calendar: &Calendar) -> Self {
+    let first = calendar.week_start_date(date);
+    return Self::dates(first, first + chrono::Days::new(6), calendar);
+  }
+
+  /// The month containing `date`.
+  pub fn month(date: chrono::NaiveDate, calendar: &Calendar) -> Self {
+    let first = date.with_day(1).unwrap();
+    let next = first + chrono::Months::new(1);
+    return Self::dates(first, next.pred_opt().unwrap_or(next), calendar);
+  }
+
+  /// The year containing `date`.
+  pub fn year(date: chrono::NaiveDate, calendar: &Calendar) -> Self {
+    let first = chrono::NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap();
+    let last = chrono::NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap();
+    return Self::dates(first, last, calendar);
+  }
+
   pub fn contains(&self, moment: &chrono::DateTime<chrono::Local>) -> bool {
-    return &self.from <= moment && moment <= &self.to;
+    return &self.from <= moment && moment < &self.to;
   }
 }
 
-pub fn get_period_since_now(period_days: i64) -> chrono::DateTime<chrono::Local> {
-  return get_checked_sub_signed_from_now(
-    chrono::Duration::days(period_days)
-      .checked_add(&get_duration_from_midnight())
-      .unwrap(),
-  );
+/// The first moment of the day at `time` in `tz`. When it's skipped by a DST
+/// transition it's the first moment after the gap, when it's repeated it's
+/// the earlier one.
+pub fn start_of_day_in<Tz: TimeZone>(
+  tz: &Tz,
+  date: chrono::NaiveDate,
+  time: chrono::NaiveTime,
+) -> chrono::DateTime<Tz> {
+  const STEP_MINUTES: i64 = 15;
+
+  let first = date.and_time(time);
+  let mut moment = first;
+  while moment - first < chrono::Duration::days(1) {
+    if let Some(start) = tz.from_local_datetime(&moment).earliest() {
+      return start;
+    }
+    moment += chrono::Duration::minutes(STEP_MINUTES);
+  }
+  // Zones never skip a whole day, but fall back to UTC anyway.
+  return tz.from_utc_datetime(&first);
 }
 
-pub fn get_week_start_datetime() -> chrono::DateTime<chrono::Local> {
-  return get_checked_sub_signed_from_now(
-    chrono::Duration::days(chrono::Local::now().weekday().num_days_from_monday().into())
-      .checked_add(&get_duration_from_midnight())
-      .unwrap(),
-  );
+/// Parses durations like `1h30m`, `45m`, `90s` or `2d`, spaces between the
+/// parts are allowed.
+pub fn parse_duration(input: &str) -> Result<chrono::Duration, String> {
+  let parse_err = || format!("can't parse duration '{input}', expected e.g. 1h30m, 45m, 90s or 2d");
+
+  let mut total = chrono::Duration::zero();
+  let mut number = String::new();
+  let mut has_units = false;
+
+  for c in input.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+    let value: i64 = number.parse().map_err(|_| parse_err())?;
+    number.clear();
+    let part = match c {
+      'd' => chrono::Duration::try_days(value),
+      'h' => chrono::Duration::try_hours(value),
+      'm' => chrono::Duration::try_minutes(value),
+      's' => chrono::Duration::try_seconds(value),
+      _ => None,
+    };
+    total = part
+      .and_then(|part| total.checked_add(&part))
+      .ok_or_else(parse_err)?;
+    has_units = true;
+  }
+
+  match has_units && number.is_empty() {
+    true => Ok(total),
+    false => Err(parse_err()),
+  }
 }
 
-pub fn get_midnight_datetime() -> chrono::DateTime<chrono::Local> {
-  return get_checked_sub_signed_from_now(get_duration_from_midnight());
+pub fn get_period_since_now(period_days: i64, calendar: &Calendar) -> chrono::DateTime<chrono::Local> {
+  let first = calendar.today() - chrono::Days::new(period_days.max(0) as u64);
+  return calendar.start_of_day(first);
 }
 
-fn get_duration_from_midnight() -> chrono::Duration {
-  return chrono::Duration::seconds(
-    chrono::Local::now()
-      .time()
-      .num_seconds_from_midnight()
-      .into(),
-  );
+pub fn get_week_start_datetime(calendar: &Calendar) -> chrono::DateTime<chrono::Local> {
+  return Period::week(calendar.today(), calendar).from;
 }
 
-fn get_checked_sub_signed_from_now(duration: chrono::Duration) -> chrono::DateTime<chrono::Local> {
-  return chrono::Local::now().checked_sub_signed(duration).unwrap();
+/// The start of the current working day.
+pub fn get_midnight_datetime(calendar: &Calendar) -> chrono::DateTime<chrono::Local> {
+  return calendar.start_of_day(calendar.today());
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone};
+
+  use super::{parse_duration, start_of_day_in, Calendar, Period};
+  use crate::{
+    task::Task,
+    time::{DateTimeInterval, DisplayZone},
+  };
+
+  /// A zone which jumps from +01:00 to +02:00 at local midnight of
+  /// 2024-03-31, so that day starts at 01:00.
+  #[derive(Clone, Copy)]
+  struct MidnightDstZone;
+
+  impl MidnightDstZone {
+    fn transition() -> NaiveDateTime {
+      NaiveDate::from_ymd_opt(2024, 3, 31).unwrap().and_hms_opt(0, 0, 0).unwrap()
+    }
+  }
+
+  impl TimeZone for MidnightDstZone {
+    type Offset = FixedOffset;
+
+    fn from_offset(_: &FixedOffset) -> Self {
+      MidnightDstZone
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+      self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+      let transition = Self::transition();
+      if *local < transition {
+        LocalResult::Single(FixedOffset::east_opt(3600).unwrap())
+      } else if *local < transition + chrono::Duration::hours(1) {
+        LocalResult::None
+      } else {
+        LocalResult::Single(FixedOffset::east_opt(7200).unwrap())
+      }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+      self.offset_from_utc_datetime(&utc.and_hms_opt(12, 0, 0).unwrap())
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+      match *utc < Self::transition() - chrono::Duration::hours(1) {
+        true => FixedOffset::east_opt(3600).unwrap(),
+        false => FixedOffset::east_opt(7200).unwrap(),
+      }
+    }
+  }
+
+  #[test]
+  fn test_start_of_day_skipped_midnight() {
+    let day = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+    let start = start_of_day_in(&MidnightDstZone, day, chrono::NaiveTime::MIN);
+    assert_eq!(start.naive_local().to_string(), "2024-03-31 01:00:00");
+    assert_eq!(start.naive_utc().to_string(), "2024-03-30 23:00:00");
+
+    let previous_day = start_of_day_in(&MidnightDstZone, day.pred_opt().unwrap(), chrono::NaiveTime::MIN);
+    assert_eq!(start - previous_day, chrono::Duration::hours(24));
+  }
+
+  #[test]
+  fn test_week_start_date() {
+    // Wednesday
+    let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+    let week_start_date = |week_start| {
+      let calendar = Calendar {
+        week_start,
+        ..Default::default()
+      };
+      calendar.week_start_date(date).to_string()
+    };
+
+    assert_eq!(week_start_date(chrono::Weekday::Mon), "2024-01-29");
+    assert_eq!(week_start_date(chrono::Weekday::Sun), "2024-01-28");
+    assert_eq!(week_start_date(chrono::Weekday::Wed), "2024-01-31");
+  }
+
+  #[test]
+  fn test_working_date_with_day_rollover() {
+    let calendar = Calendar {
+      day_starts_at: chrono::NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      ..Default::default()
+    };
+    let at = |time: &str| {
+      let moment = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+      calendar.working_date(&chrono::Utc.from_utc_datetime(&moment)).to_string()
+    };
+
+    assert_eq!(at("2024-01-31 01:30"), "2024-01-30");
+    assert_eq!(at("2024-01-31 04:00"), "2024-01-31");
+    assert_eq!(at("2024-01-31 23:59"), "2024-01-31");
+  }
+
+  #[test]
+  fn test_split_by_day() {
+    let calendar = Calendar {
+      day_starts_at: chrono::NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+      ..Default::default()
+    };
+    let interval: DateTimeInterval = serde_json::from_str(
+      r#"{"start_time": "2024-01-30T22:00:00+01:00", "stop_time": "2024-02-01T05:00:00+01:00"}"#,
+    )
+    .unwrap();
+
+    let parts: Vec<(String, String)> = calendar
+      .split_by_day(&interval, DisplayZone::Recorded)
+      .iter()
+      .map(|(date, part)| {
+        let duration = part.duration();
+        (date.to_string(), format!("{}h", duration.num_hours()))
+      })
+      .collect();
+
+    assert_eq!(
+      parts,
+      vec![
+        ("2024-01-30".to_string(), "6h".to_string()),
+        ("2024-01-31".to_string(), "24h".to_string()),
+        ("2024-02-01".to_string(), "1h".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_running_task_clipped_to_now_keeps_running() {
+    let start_time = chrono::Local::now() - chrono::Duration::hours(1);
+    let task = Task::new(uuid::Uuid::new_v4(), "running", vec![], Some(start_time), None);
+    let period = Period::new_to_now(start_time - chrono::Duration::hours(1));
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    let clipped = task.clipped(&period).unwrap();
+
+    assert_eq!(clipped.times()[0].stop_time, None);
+    assert!(clipped.duration() >= chrono::Duration::hours(1));
+  }
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("1h30m"), Ok(chrono::Duration::minutes(90)));
+    assert_eq!(parse_duration("2d 1s"), Ok(chrono::Duration::seconds(2 * 86400 + 1)));
+    assert_eq!(parse_duration("45M"), Ok(chrono::Duration::minutes(45)));
+    assert!(parse_duration("90").is_err());
+    assert!(parse_duration("1x").is_err());
+    assert!(parse_duration("").is_err());
+  }
 }
@@ -0,0 +1,89 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Value of a custom task field. Kept as JSON bool, number or string, so the
+/// type survives the edit views.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+  Bool(bool),
+  Integer(i64),
+  Number(f64),
+  Text(String),
+}
+
+impl FieldValue {
+  /// Guesses the type of a value typed in the command line, anything which
+  /// isn't a bool or a number stays text.
+  pub fn parse(value: &str) -> Self {
+    if let Ok(value) = value.parse::<bool>() {
+      return FieldValue::Bool(value);
+    }
+    if let Ok(value) = value.parse::<i64>() {
+      return FieldValue::Integer(value);
+    }
+    if let Ok(value) = value.parse::<f64>() {
+      if value.is_finite() {
+        return FieldValue::Number(value);
+      }
+    }
+    return FieldValue::Text(value.to_owned());
+  }
+}
+
+impl Display for FieldValue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FieldValue::Bool(value) => write!(f, "{value}"),
+      FieldValue::Integer(value) => write!(f, "{value}"),
+      FieldValue::Number(value) => write!(f, "{value}"),
+      FieldValue::Text(value) => write!(f, "{value}"),
+    }
+  }
+}
+
+pub type Fields = BTreeMap<String, FieldValue>;
+
+/// Splits `key=value` into the key and the raw value.
+pub fn split_field(input: &str) -> Result<(String, String), String> {
+  match input.split_once('=') {
+    Some((key, value)) if !key.trim().is_empty() => {
+      Ok((key.trim().to_owned(), value.trim().to_owned()))
+    }
+    _ => Err(format!("can't parse field '{input}', expected key=value")),
+  }
+}
+
+pub fn parse_fields(inputs: &[String]) -> Result<Fields, String> {
+  let mut fields = Fields::new();
+  for input in inputs {
+    let (key, value) = split_field(input)?;
+    fields.insert(key, FieldValue::parse(&value));
+  }
+  return Ok(fields);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse_fields, FieldValue};
+
+  #[test]
+  fn test_parse_fields() {
+    let inputs: Vec<String> = ["ticket=ENG-42", "po = 1042", "rate=1.5", "urgent=true"]
+      .iter()
+      .map(|s| s.to_string())
+      .collect();
+
+    let fields = parse_fields(&inputs).unwrap();
+
+    assert_eq!(fields["ticket"], FieldValue::Text("ENG-42".to_string()));
+    assert_eq!(fields["po"], FieldValue::Integer(1042));
+    assert_eq!(fields["rate"], FieldValue::Number(1.5));
+    assert_eq!(fields["urgent"], FieldValue::Bool(true));
+    assert_eq!(
+      serde_json::to_string(&fields).unwrap(),
+      r#"{"po":1042,"rate":1.5,"ticket":"ENG-42","urgent":true}"#
+    );
+    assert!(parse_fields(&["=x".to_string()]).is_err());
+    assert!(parse_fields(&["novalue".to_string()]).is_err());
+  }
+}
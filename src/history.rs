@@ -0,0 +1,151 @@
+use std::io::Write;
+
+/// Fields which never change or change with every revision, so they say
+/// nothing about the change.
+const IGNORED_FIELDS: [&str; 4] = ["id", "created_at", "updated_at", "updated_by_device"];
+
+/// A task as it was right after a change, `task: None` means it was removed.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TaskRevision {
+  pub time: chrono::DateTime<chrono::Local>,
+  pub task_id: uuid::Uuid,
+  pub message: String,
+  pub task: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+  pub field: String,
+  pub before: Option<serde_json::Value>,
+  pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskHistoryEntry {
+  pub time: chrono::DateTime<chrono::Local>,
+  pub device: Option<uuid::Uuid>,
+  pub message: String,
+  pub changes: Vec<FieldChange>,
+}
+
+/// Turns consecutive revisions of a task into field-level changes. Revisions
+/// which didn't change anything are skipped.
+pub fn build_task_history(revisions: &[TaskRevision]) -> Vec<TaskHistoryEntry> {
+  let mut entries = Vec::new();
+  let mut previous: Option<&serde_json::Value> = None;
+
+  for revision in revisions.iter() {
+    let changes = diff_fields(previous, revision.task.as_ref());
+    if !changes.is_empty() {
+      entries.push(TaskHistoryEntry {
+        time: revision.time,
+        device: revision
+          .task
+          .as_ref()
+          .and_then(|task| task.get("updated_by_device")?.as_str()?.parse().ok()),
+        message: revision.message.clone(),
+        changes,
+      });
+    }
+    previous = revision.task.as_ref();
+  }
+  return entries;
+}
+
+pub fn diff_fields(
+  before: Option<&serde_json::Value>,
+  after: Option<&serde_json::Value>,
+) -> Vec<FieldChange> {
+  let empty = serde_json::Map::new();
+  let before_fields = before.and_then(|b| b.as_object()).unwrap_or(&empty);
+  let after_fields = after.and_then(|a| a.as_object()).unwrap_or(&empty);
+
+  let mut fields: Vec<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+  fields.sort();
+  fields.dedup();
+
+  return fields
+    .into_iter()
+    .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+    .filter(|&field| before_fields.get(field) != after_fields.get(field))
+    .map(|field| FieldChange {
+      field: field.clone(),
+      before: before_fields.get(field).cloned(),
+      after: after_fields.get(field).cloned(),
+    })
+    .collect();
+}
+
+/// Append-only log of task revisions for syncers which don't keep history
+/// themselves. It's local to the device.
+pub struct ChangeLog {
+  filepath: std::path::PathBuf,
+}
+
+impl ChangeLog {
+  pub const FILENAME: &'static str = ".task_history.jsonl";
+
+  pub fn new(main_folder_path: &str) -> Self {
+    Self {
+      filepath: std::path::Path::new(main_folder_path).join(Self::FILENAME),
+    }
+  }
+
+  pub fn append(&self, revision: &TaskRevision) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(&self.filepath)?;
+    writeln!(file, "{}", serde_json::to_string(revision)?)
+  }
+
+  pub fn task_revisions(&self, task_id: uuid::Uuid) -> Vec<TaskRevision> {
+    std::fs::read_to_string(&self.filepath)
+      .unwrap_or_default()
+      .lines()
+      .filter_map(|line| serde_json::from_str::<TaskRevision>(line).ok())
+      .filter(|revision| revision.task_id == task_id)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{build_task_history, diff_fields, TaskRevision};
+
+  #[test]
+  fn test_diff_fields_skips_ignored_fields() {
+    let before = serde_json::json!({"title": "a", "is_paused": false, "updated_at": "1"});
+    let after = serde_json::json!({"title": "b", "is_paused": false, "updated_at": "2"});
+
+    let changes = diff_fields(Some(&before), Some(&after));
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].field, "title");
+    assert_eq!(changes[0].before, Some(serde_json::json!("a")));
+    assert_eq!(changes[0].after, Some(serde_json::json!("b")));
+  }
+
+  #[test]
+  fn test_build_task_history() {
+    let task_id = uuid::Uuid::new_v4();
+    let revision = |message: &str, task: Option<serde_json::Value>| TaskRevision {
+      time: chrono::Local::now(),
+      task_id,
+      message: message.to_string(),
+      task,
+    };
+    let revisions = vec![
+      revision("started", Some(serde_json::json!({"title": "a"}))),
+      revision("replace", Some(serde_json::json!({"title": "a"}))),
+      revision("replace", Some(serde_json::json!({"title": "b"}))),
+      revision("removed", None),
+    ];
+
+    let history = build_task_history(&revisions);
+
+    let messages: Vec<&str> = history.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, vec!["started", "replace", "removed"]);
+    assert_eq!(history[2].changes[0].after, None);
+  }
+}
@@ -0,0 +1,54 @@
+/// Jira-like keys (`ENG-1234`) and GitHub-like numbers (`#42`).
+pub const DEFAULT_ISSUE_PATTERNS: [&str; 2] = [r"\b[A-Z][A-Z0-9]+-\d+\b", r"#\d+\b"];
+
+/// Compiles the configured patterns, invalid ones are skipped with a warning.
+pub fn compile_patterns(patterns: &[String]) -> Vec<regex::Regex> {
+  patterns
+    .iter()
+    .filter_map(|pattern| match regex::Regex::new(pattern) {
+      Ok(regex) => Some(regex),
+      Err(err) => {
+        log::warn!("skip invalid issue pattern '{pattern}': {err}");
+        None
+      }
+    })
+    .collect()
+}
+
+/// Issue references found in the title, in the order they appear. When a
+/// pattern has a capture group, the first group is the reference.
+pub fn extract_issues(title: &str, patterns: &[regex::Regex]) -> Vec<String> {
+  let mut found: Vec<(usize, String)> = Vec::new();
+  for pattern in patterns {
+    for captures in pattern.captures_iter(title) {
+      let Some(reference) = captures.get(1).or(captures.get(0)) else {
+        continue;
+      };
+      if !found.iter().any(|(_, known)| known == reference.as_str()) {
+        found.push((reference.start(), reference.as_str().to_owned()));
+      }
+    }
+  }
+  found.sort_by_key(|(start, _)| *start);
+  return found.into_iter().map(|(_, reference)| reference).collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{compile_patterns, extract_issues, DEFAULT_ISSUE_PATTERNS};
+
+  #[test]
+  fn test_extract_issues() {
+    let patterns: Vec<String> = DEFAULT_ISSUE_PATTERNS.iter().map(|p| p.to_string()).collect();
+    let patterns = compile_patterns(&patterns);
+
+    assert_eq!(
+      extract_issues("review #42 and ENG-1234, then ENG-1234 again", &patterns),
+      vec!["#42", "ENG-1234"]
+    );
+    assert!(extract_issues("no eng-1 nor #abc here", &patterns).is_empty());
+
+    let patterns = compile_patterns(&[r"ticket (\d+)".to_string(), "(".to_string()]);
+    assert_eq!(extract_issues("fix ticket 77", &patterns), vec!["77"]);
+  }
+}
@@ -7,12 +7,22 @@ extern crate uuid;
 mod busy;
 mod config;
 
+pub mod billing;
+pub mod budget;
 pub mod duration;
+pub mod fields;
+pub mod history;
+pub mod issues;
+pub mod overlap;
 pub mod project;
+pub mod record_meta;
+pub mod recurring;
+pub mod schedule;
 pub mod storage;
 pub mod sync;
 pub mod tag;
 pub mod task;
+pub mod template;
 pub mod time;
 pub mod traits;
 
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use crate::{task::Task, traits::Indexable};
+
+/// Time of each task with the moments several tasks ran at once divided
+/// equally between them, so the durations add up to the wall-clock time.
+pub fn split_overlaps(tasks: &[Task]) -> HashMap<uuid::Uuid, chrono::Duration> {
+  let now = chrono::Utc::now();
+  let intervals: Vec<(uuid::Uuid, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = tasks
+    .iter()
+    .flat_map(|task| {
+      task
+        .times()
+        .iter()
+        .map(move |interval| (task.id(), interval.start_time, interval.stop_time.unwrap_or(now)))
+    })
+    .collect();
+
+  let mut bounds: Vec<chrono::DateTime<chrono::Utc>> = intervals
+    .iter()
+    .flat_map(|(_, start, stop)| [*start, *stop])
+    .collect();
+  bounds.sort();
+  bounds.dedup();
+
+  let mut durations: HashMap<uuid::Uuid, chrono::Duration> = tasks
+    .iter()
+    .map(|task| (task.id(), chrono::Duration::zero()))
+    .collect();
+  for segment in bounds.windows(2) {
+    let running: Vec<uuid::Uuid> = intervals
+      .iter()
+      .filter(|(_, start, stop)| *start <= segment[0] && segment[1] <= *stop)
+      .map(|(id, _, _)| *id)
+      .collect();
+    if running.is_empty() {
+      continue;
+    }
+    let share = (segment[1] - segment[0]) / running.len() as i32;
+    for id in running {
+      *durations.get_mut(&id).unwrap() += share;
+    }
+  }
+  return durations;
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+
+  use super::split_overlaps;
+  use crate::{task::Task, traits::Indexable};
+
+  #[test]
+  fn test_split_overlaps() {
+    let at = |hour, minute| chrono::Local.with_ymd_and_hms(2026, 10, 14, hour, minute, 0).unwrap();
+    let project_id = uuid::Uuid::new_v4();
+    let meeting = Task::new(project_id, "meeting", vec![], Some(at(10, 0)), Some(at(11, 0)));
+    let incident = Task::new(project_id, "incident", vec![], Some(at(10, 30)), Some(at(12, 0)));
+    let later = Task::new(project_id, "later", vec![], Some(at(13, 0)), Some(at(13, 20)));
+
+    let durations = split_overlaps(&[meeting.clone(), incident.clone(), later.clone()]);
+
+    assert_eq!(durations[&meeting.id()].num_minutes(), 45);
+    assert_eq!(durations[&incident.id()].num_minutes(), 75);
+    assert_eq!(durations[&later.id()].num_minutes(), 20);
+  }
+}
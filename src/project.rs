@@ -1,9 +1,32 @@
-use crate::traits::Indexable;
+use crate::{
+  billing::Rate,
+  budget::Budget,
+  record_meta::RecordMeta,
+  traits::{Indexable, Trackable},
+};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Project {
   id: uuid::Uuid,
   name: String,
+  #[serde(default)]
+  rate: Option<Rate>,
+  /// Default for the project tasks, a task can override it.
+  #[serde(default = "default_billable")]
+  billable: bool,
+  #[serde(default)]
+  budget: Option<Budget>,
+  /// Archived projects are hidden from lists and completions.
+  #[serde(default)]
+  archived: bool,
+  /// Colour of the project name, e.g. `blue` or `bright magenta`.
+  #[serde(default)]
+  color: Option<String>,
+  /// Tag names added to every new task of the project.
+  #[serde(default)]
+  default_tags: Vec<String>,
+  #[serde(flatten)]
+  meta: RecordMeta,
 }
 
 impl Indexable for Project {
@@ -12,15 +35,99 @@ impl Indexable for Project {
   }
 }
 
+impl Trackable for Project {
+  fn meta(&self) -> &RecordMeta {
+    &self.meta
+  }
+
+  fn meta_mut(&mut self) -> &mut RecordMeta {
+    &mut self.meta
+  }
+}
+
 impl Project {
+  pub const PATH_SEPARATOR: char = '/';
+
   pub fn new(name: &str) -> Self {
     Self {
       id: uuid::Uuid::new_v4(),
       name: name.to_owned(),
+      rate: None,
+      billable: true,
+      budget: None,
+      archived: false,
+      color: None,
+      default_tags: Vec::new(),
+      meta: RecordMeta::default(),
     }
   }
 
   pub fn name(&self) -> &str {
     self.name.as_str()
   }
+
+  /// Projects are nested by their names, `acme/website/backend` is a
+  /// workstream of the `acme/website` project of the `acme` client.
+  pub fn path(&self) -> Vec<&str> {
+    self
+      .name
+      .split(Self::PATH_SEPARATOR)
+      .filter(|part| !part.is_empty())
+      .collect()
+  }
+
+  /// Whether the project is `ancestor` itself or one of its descendants.
+  pub fn is_within(&self, ancestor: &str) -> bool {
+    let ancestor: Vec<&str> = ancestor
+      .split(Self::PATH_SEPARATOR)
+      .filter(|part| !part.is_empty())
+      .collect();
+    return !ancestor.is_empty() && self.path().starts_with(&ancestor);
+  }
+
+  pub fn rate(&self) -> Option<&Rate> {
+    self.rate.as_ref()
+  }
+
+  pub fn is_billable(&self) -> bool {
+    self.billable
+  }
+
+  pub fn budget(&self) -> Option<&Budget> {
+    self.budget.as_ref()
+  }
+
+  pub fn is_archived(&self) -> bool {
+    self.archived
+  }
+
+  pub fn color(&self) -> Option<&str> {
+    self.color.as_deref()
+  }
+
+  pub fn default_tags(&self) -> &Vec<String> {
+    &self.default_tags
+  }
+}
+
+fn default_billable() -> bool {
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Project;
+
+  #[test]
+  fn test_is_within() {
+    let project = Project::new("acme/website/backend");
+
+    assert_eq!(project.path(), vec!["acme", "website", "backend"]);
+    assert!(project.is_within("acme"));
+    assert!(project.is_within("acme/website/"));
+    assert!(project.is_within("acme/website/backend"));
+    assert!(!project.is_within("acme/web"));
+    assert!(!project.is_within("acme/website/backend/api"));
+    assert!(!project.is_within(""));
+  }
 }
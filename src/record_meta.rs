@@ -0,0 +1,20 @@
+/// When and where a task, project or tag was created and last changed.
+/// Empty for records created before these fields were introduced.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecordMeta {
+  #[serde(default)]
+  pub created_at: Option<chrono::DateTime<chrono::Local>>,
+  #[serde(default)]
+  pub updated_at: Option<chrono::DateTime<chrono::Local>>,
+  #[serde(default)]
+  pub updated_by_device: Option<uuid::Uuid>,
+}
+
+impl RecordMeta {
+  pub fn touch(&mut self, device_id: uuid::Uuid) {
+    let now = chrono::Local::now();
+    self.created_at.get_or_insert(now);
+    self.updated_at = Some(now);
+    self.updated_by_device = Some(device_id);
+  }
+}
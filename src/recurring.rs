@@ -0,0 +1,265 @@
+use std::collections::BTreeSet;
+
+use chrono::Datelike;
+
+use crate::duration::start_of_day_in;
+
+/// Custom field marking tasks added for a recurring entry, the value is the
+/// template name.
+pub const RECURRING_FIELD: &str = "recurring";
+
+const RECURRING_NAMESPACE: uuid::Uuid = uuid::uuid!("5d0c3e0e-8f43-4b8e-9a55-1f0a3c4b7e21");
+
+/// Days a recurring entry happens on. Accepts `daily`, `weekdays`,
+/// `weekends`, `weekly on tue,thu` or an RRULE with `FREQ=DAILY` or
+/// `FREQ=WEEKLY` and an optional `BYDAY`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RecurrenceRule {
+  text: String,
+  /// Indexed by days from Monday.
+  days: [bool; 7],
+}
+
+impl RecurrenceRule {
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let rule = text.trim().to_lowercase();
+    let days = match rule.as_str() {
+      "daily" | "every day" => [true; 7],
+      "weekdays" => [true, true, true, true, true, false, false],
+      "weekends" => [false, false, false, false, false, true, true],
+      _ => match rule.strip_prefix("rrule:").unwrap_or(&rule) {
+        rrule if rrule.starts_with("freq=") => parse_rrule(rrule)?,
+        _ => {
+          let days = rule
+            .strip_prefix("weekly")
+            .unwrap_or(&rule)
+            .trim_start()
+            .strip_prefix("on")
+            .unwrap_or(&rule);
+          parse_weekdays(days, |day| day.parse().ok())?
+        }
+      },
+    };
+
+    return match days.contains(&true) {
+      true => Ok(Self {
+        text: text.trim().to_owned(),
+        days,
+      }),
+      false => Err(format!("rule '{text}' has no days")),
+    };
+  }
+
+  pub fn matches(&self, date: chrono::NaiveDate) -> bool {
+    self.days[date.weekday().num_days_from_monday() as usize]
+  }
+}
+
+impl TryFrom<String> for RecurrenceRule {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    Self::parse(&value)
+  }
+}
+
+impl From<RecurrenceRule> for String {
+  fn from(rule: RecurrenceRule) -> Self {
+    rule.text
+  }
+}
+
+fn parse_rrule(rrule: &str) -> Result<[bool; 7], String> {
+  let mut freq = None;
+  let mut days = None;
+  for part in rrule.split(';').filter(|part| !part.is_empty()) {
+    match part.split_once('=') {
+      Some(("freq", value)) => freq = Some(value),
+      Some(("byday", value)) => days = Some(parse_weekdays(value, rrule_weekday)?),
+      _ => return Err(format!("unsupported rule part '{part}'")),
+    }
+  }
+
+  return match (freq, days) {
+    (Some("daily"), None) => Ok([true; 7]),
+    (Some("daily") | Some("weekly"), Some(days)) => Ok(days),
+    (Some("weekly"), None) => Err("weekly rules need BYDAY".to_string()),
+    _ => Err(format!("unsupported rule '{rrule}', use FREQ=DAILY or FREQ=WEEKLY")),
+  };
+}
+
+fn parse_weekdays(
+  days: &str,
+  parse_day: impl Fn(&str) -> Option<chrono::Weekday>,
+) -> Result<[bool; 7], String> {
+  let mut result = [false; 7];
+  for day in days.split(|c: char| c == ',' || c.is_whitespace()) {
+    if day.is_empty() || day == "and" {
+      continue;
+    }
+    let weekday = parse_day(day).ok_or(format!("unknown day '{day}'"))?;
+    result[weekday.num_days_from_monday() as usize] = true;
+  }
+  return Ok(result);
+}
+
+fn rrule_weekday(day: &str) -> Option<chrono::Weekday> {
+  match day {
+    "mo" => Some(chrono::Weekday::Mon),
+    "tu" => Some(chrono::Weekday::Tue),
+    "we" => Some(chrono::Weekday::Wed),
+    "th" => Some(chrono::Weekday::Thu),
+    "fr" => Some(chrono::Weekday::Fri),
+    "sa" => Some(chrono::Weekday::Sat),
+    "su" => Some(chrono::Weekday::Sun),
+    _ => None,
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum RecurringMode {
+  /// Added as a finished task once it's over.
+  Auto,
+  /// Offered by `busy today` to be added or dismissed.
+  #[default]
+  Confirm,
+}
+
+/// A task repeating on a schedule, e.g. a daily standup from a template.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecurringEntry {
+  pub template: String,
+  pub rule: RecurrenceRule,
+  pub start: chrono::NaiveTime,
+  pub finish: chrono::NaiveTime,
+  #[serde(default)]
+  pub mode: RecurringMode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+  pub entry: RecurringEntry,
+  pub date: chrono::NaiveDate,
+  pub start: chrono::DateTime<chrono::Local>,
+  pub finish: chrono::DateTime<chrono::Local>,
+}
+
+impl Occurrence {
+  pub fn key(&self) -> String {
+    format!("{}/{}", self.entry.template, self.date)
+  }
+
+  /// Id of the task added for the occurrence. It's the same on every device,
+  /// so devices adding the same occurrence write the same record.
+  pub fn task_id(&self) -> uuid::Uuid {
+    uuid::Uuid::new_v5(&RECURRING_NAMESPACE, self.key().as_bytes())
+  }
+}
+
+/// Occurrences of the entries from `first` to `last`, holidays are skipped.
+pub fn occurrences(
+  entries: &[RecurringEntry],
+  holidays: &[chrono::NaiveDate],
+  first: chrono::NaiveDate,
+  last: chrono::NaiveDate,
+) -> Vec<Occurrence> {
+  let mut result = Vec::new();
+  for date in first.iter_days().take_while(|date| *date <= last) {
+    if holidays.contains(&date) {
+      continue;
+    }
+    for entry in entries.iter().filter(|entry| entry.rule.matches(date)) {
+      result.push(Occurrence {
+        entry: entry.clone(),
+        date,
+        start: start_of_day_in(&chrono::Local, date, entry.start),
+        finish: start_of_day_in(&chrono::Local, date, entry.finish),
+      });
+    }
+  }
+  result.sort_by_key(|occurrence| occurrence.start);
+  return result;
+}
+
+/// Local bookkeeping of recurring entries, not synced: up to when entries
+/// were added automatically and which offered ones were dismissed.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RecurringState {
+  pub checked_until: Option<chrono::DateTime<chrono::Local>>,
+  pub dismissed: BTreeSet<String>,
+  #[serde(skip)]
+  filepath: std::path::PathBuf,
+}
+
+impl RecurringState {
+  pub const FILENAME: &'static str = ".recurring_state.json";
+
+  pub fn restore(main_folder_path: &str) -> Self {
+    let filepath = std::path::Path::new(main_folder_path).join(Self::FILENAME);
+    let mut state: Self = std::fs::read(&filepath)
+      .ok()
+      .and_then(|content| serde_json::from_slice(&content).ok())
+      .unwrap_or_default();
+    state.filepath = filepath;
+    return state;
+  }
+
+  pub fn save(&self) {
+    let content = serde_json::to_string_pretty(self).unwrap();
+    if let Err(err) = std::fs::write(&self.filepath, content) {
+      log::debug!("can't save recurring state to {:?}: {err}", self.filepath);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{occurrences, RecurrenceRule, RecurringEntry, RecurringMode};
+
+  #[test]
+  fn test_recurrence_rules() {
+    // Mon 2026-10-12 .. Sun 2026-10-18
+    let week: Vec<chrono::NaiveDate> = chrono::NaiveDate::from_ymd_opt(2026, 10, 12)
+      .unwrap()
+      .iter_days()
+      .take(7)
+      .collect();
+    let days_of = |rule: &str| -> Vec<usize> {
+      let rule = RecurrenceRule::parse(rule).unwrap();
+      (0..7).filter(|&i| rule.matches(week[i])).collect()
+    };
+
+    assert_eq!(days_of("weekdays"), vec![0, 1, 2, 3, 4]);
+    assert_eq!(days_of("daily"), vec![0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(days_of("weekly on Tue"), vec![1]);
+    assert_eq!(days_of("mon, thursday"), vec![0, 3]);
+    assert_eq!(days_of("FREQ=WEEKLY;BYDAY=TU,FR"), vec![1, 4]);
+    assert_eq!(days_of("RRULE:FREQ=DAILY"), vec![0, 1, 2, 3, 4, 5, 6]);
+    assert!(RecurrenceRule::parse("FREQ=MONTHLY").is_err());
+    assert!(RecurrenceRule::parse("FREQ=WEEKLY").is_err());
+    assert!(RecurrenceRule::parse("someday").is_err());
+  }
+
+  #[test]
+  fn test_occurrences_skip_holidays() {
+    let entry = RecurringEntry {
+      template: "standup".to_string(),
+      rule: RecurrenceRule::parse("weekdays").unwrap(),
+      start: chrono::NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+      finish: chrono::NaiveTime::from_hms_opt(9, 45, 0).unwrap(),
+      mode: RecurringMode::Auto,
+    };
+    let monday = chrono::NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+    let holidays = vec![monday + chrono::Days::new(1)];
+
+    let found = occurrences(&[entry], &holidays, monday, monday + chrono::Days::new(6));
+
+    let dates: Vec<u32> = found.iter().map(|o| chrono::Datelike::day(&o.date)).collect();
+    assert_eq!(dates, vec![12, 14, 15, 16]);
+    assert_eq!(found[0].key(), "standup/2026-10-12");
+    assert_eq!(found[0].task_id(), found[0].clone().task_id());
+    assert_ne!(found[0].task_id(), found[1].task_id());
+    assert_eq!((found[0].finish - found[0].start).num_minutes(), 15);
+  }
+}
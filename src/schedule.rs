@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+
+use crate::budget::TimeAmount;
+
+/// Hours to work on each day of the week, days without hours are days off.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Schedule {
+  #[serde(rename = "Mon", default, skip_serializing_if = "Option::is_none")]
+  mon: Option<TimeAmount>,
+  #[serde(rename = "Tue", default, skip_serializing_if = "Option::is_none")]
+  tue: Option<TimeAmount>,
+  #[serde(rename = "Wed", default, skip_serializing_if = "Option::is_none")]
+  wed: Option<TimeAmount>,
+  #[serde(rename = "Thu", default, skip_serializing_if = "Option::is_none")]
+  thu: Option<TimeAmount>,
+  #[serde(rename = "Fri", default, skip_serializing_if = "Option::is_none")]
+  fri: Option<TimeAmount>,
+  #[serde(rename = "Sat", default, skip_serializing_if = "Option::is_none")]
+  sat: Option<TimeAmount>,
+  #[serde(rename = "Sun", default, skip_serializing_if = "Option::is_none")]
+  sun: Option<TimeAmount>,
+}
+
+impl Schedule {
+  pub fn is_empty(&self) -> bool {
+    self == &Self::default()
+  }
+
+  pub fn target(&self, date: chrono::NaiveDate) -> chrono::Duration {
+    let hours = match date.weekday() {
+      chrono::Weekday::Mon => self.mon,
+      chrono::Weekday::Tue => self.tue,
+      chrono::Weekday::Wed => self.wed,
+      chrono::Weekday::Thu => self.thu,
+      chrono::Weekday::Fri => self.fri,
+      chrono::Weekday::Sat => self.sat,
+      chrono::Weekday::Sun => self.sun,
+    };
+    return hours.map_or(chrono::Duration::zero(), |hours| hours.0);
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayBalance {
+  pub date: chrono::NaiveDate,
+  pub worked: chrono::Duration,
+  pub target: chrono::Duration,
+}
+
+impl DayBalance {
+  /// Overtime when positive, undertime when negative.
+  pub fn difference(&self) -> chrono::Duration {
+    self.worked - self.target
+  }
+}
+
+/// Worked time against the schedule for every day from `first` to `last`,
+/// days without work included.
+pub fn daily_balance(
+  schedule: &Schedule,
+  worked: &BTreeMap<chrono::NaiveDate, chrono::Duration>,
+  first: chrono::NaiveDate,
+  last: chrono::NaiveDate,
+) -> Vec<DayBalance> {
+  return first
+    .iter_days()
+    .take_while(|date| *date <= last)
+    .map(|date| DayBalance {
+      date,
+      worked: worked.get(&date).copied().unwrap_or(chrono::Duration::zero()),
+      target: schedule.target(date),
+    })
+    .collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::{daily_balance, Schedule};
+
+  #[test]
+  fn test_daily_balance() {
+    let schedule: Schedule = serde_json::from_str(r#"{"Thu": "8h", "Fri": "6h"}"#).unwrap();
+    let thursday = chrono::NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+    let worked = BTreeMap::from([(thursday, chrono::Duration::hours(9))]);
+
+    let balance = daily_balance(&schedule, &worked, thursday, thursday + chrono::Days::new(2));
+
+    let differences: Vec<i64> = balance.iter().map(|day| day.difference().num_hours()).collect();
+    assert_eq!(differences, vec![1, -6, 0]);
+    assert_eq!(
+      serde_json::to_string(&schedule).unwrap(),
+      r#"{"Thu":"8h","Fri":"6h"}"#
+    );
+  }
+}
@@ -1,4 +1,4 @@
-use std::io::{Seek, Write};
+use std::io::{Read, Seek, Write};
 
 use crate::{project::Project, tag::Tag, task::Task, traits::Indexable};
 
@@ -11,15 +11,18 @@ pub struct JsonStorage {
 }
 
 impl JsonStorage {
+  pub const TASKS_FILENAME: &'static str = "tasks.json";
+  pub const FILENAMES: [&'static str; 3] = [Self::TASKS_FILENAME, "projects.json", "tags.json"];
+
   pub fn new(database_folder: &str) -> Self {
     let database_path = std::path::Path::new(database_folder);
 
     let join_path = |filename: &str| database_path.join(filename).to_str().unwrap().to_owned();
 
     Self {
-      tasks: JsonStorageItem::new(join_path("tasks.json").as_str()),
-      projects: JsonStorageItem::new(join_path("projects.json").as_str()),
-      tags: JsonStorageItem::new(join_path("tags.json").as_str()),
+      tasks: JsonStorageItem::new(join_path(Self::FILENAMES[0]).as_str()),
+      projects: JsonStorageItem::new(join_path(Self::FILENAMES[1]).as_str()),
+      tags: JsonStorageItem::new(join_path(Self::FILENAMES[2]).as_str()),
     }
   }
 
@@ -197,7 +200,16 @@ where
 
   fn restore(&mut self) {
     self.file.rewind().unwrap();
-    self.buffer = serde_json::from_reader(&self.file).unwrap_or_default();
+    let mut content = String::new();
+    _ = self.file.read_to_string(&mut content);
+
+    // Items in an older format, e.g. local times without the recorded
+    // offset, are converted when they are read and saved in the current
+    // format on the next change, so reading never writes the file.
+    match serde_json::from_str(&content) {
+      Ok(buffer) => self.buffer = buffer,
+      Err(_) => self.buffer = Vec::new(),
+    }
   }
 
   fn add(&mut self, item: T) {
@@ -345,4 +357,16 @@ mod test {
     assert_eq!(all_items.len(), 1);
     assert_eq!(all_items[0].title(), "Hello, world!");
   }
+
+  #[test]
+  fn storage_item_restore_doesnt_rewrite_file() {
+    let tmp_file = tempfile::NamedTempFile::new().unwrap();
+    let content = format!(r#"[{{"id":"{}","title":"Hello"}}]"#, uuid::Uuid::new_v4());
+    std::fs::write(tmp_file.path(), &content).unwrap();
+
+    let storage = JsonStorageItem::<TestType>::new(tmp_file.path().to_str().unwrap());
+
+    assert_eq!(storage.all().len(), 1);
+    assert_eq!(std::fs::read_to_string(tmp_file.path()).unwrap(), content);
+  }
 }
@@ -5,5 +5,36 @@ pub enum SyncerConfig {
     key_file: Option<String>,
     remote: String,
     remote_branch: Option<String>,
+    /// Collect local changes into a single commit per sync instead of
+    /// committing every start/stop/pause.
+    #[serde(default)]
+    batch_commits: bool,
   },
+  S3 {
+    endpoint: String,
+    bucket: String,
+    prefix: Option<String>,
+    region: Option<String>,
+    access_key: String,
+    secret_key: String,
+  },
+  /// Self-hosted `busy-server`.
+  Http {
+    url: String,
+    database: String,
+    token: Option<String>,
+  },
+}
+
+/// When `busy` syncs on its own, without an explicit `busy sync`.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub enum AutoSyncPolicy {
+  #[default]
+  Never,
+  /// Sync after every command that changes the database.
+  OnMutation,
+  /// Sync at most once per `minutes`, checked on every command.
+  Debounced { minutes: i64 },
+  /// Sync on the first command of each day.
+  FirstCommandOfDay,
 }
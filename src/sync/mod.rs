@@ -1,9 +1,20 @@
 mod config;
+pub mod protocol;
+mod report;
+mod s3_client;
+pub mod server;
+mod state;
 mod syncer;
 mod syncer_empty;
 mod syncer_git;
+mod syncer_http;
+mod syncer_s3;
 
-pub use config::SyncerConfig;
+pub use config::{AutoSyncPolicy, SyncerConfig};
+pub use report::{diff_database_file, ChangeKind, RecordChange, SyncReport, SyncStatus};
+pub use state::SyncState;
 pub use syncer::Syncer;
 pub use syncer_empty::EmptySyncer;
 pub use syncer_git::GitSyncer;
+pub use syncer_http::HttpSyncer;
+pub use syncer_s3::S3Syncer;
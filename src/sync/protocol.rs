@@ -0,0 +1,41 @@
+//! JSON protocol between the `Http` syncer and `busy-server`.
+//!
+//! A device sends its records changed since the last sync together with the
+//! server cursor it has seen, the server answers with every record changed by
+//! other devices after that cursor and the new cursor.
+
+/// A new version of a task, project or tag, `record: None` means it was removed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecordDelta {
+  /// Database file name without extension: `tasks`, `projects` or `tags`.
+  pub collection: String,
+  pub id: uuid::Uuid,
+  pub record: Option<serde_json::Value>,
+  /// When the record was removed, compared with `updated_at` of the other
+  /// versions like an update.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub deleted_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SyncRequest {
+  pub device_id: uuid::Uuid,
+  pub cursor: u64,
+  pub changes: Vec<RecordDelta>,
+  /// Report what would be exchanged without applying `changes`.
+  #[serde(default)]
+  pub dry_run: bool,
+  /// Remove every server record which isn't in `changes`.
+  #[serde(default)]
+  pub replace: bool,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SyncResponse {
+  pub cursor: u64,
+  pub changes: Vec<RecordDelta>,
+}
+
+pub fn sync_path(database: &str) -> String {
+  format!("/databases/{database}/sync")
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+  Added,
+  Changed,
+  Removed,
+}
+
+/// A single task, project or tag which differs between two database snapshots.
+#[derive(Debug, Clone)]
+pub struct RecordChange {
+  pub kind: ChangeKind,
+  /// Database file name without extension: `tasks`, `projects` or `tags`.
+  pub collection: String,
+  pub id: uuid::Uuid,
+  /// Task title or project/tag name.
+  pub title: String,
+}
+
+/// Changes exchanged with the remote during a sync, or the ones a sync would
+/// exchange in case of a dry run.
+#[derive(Debug, Clone, Default)]
+pub struct SyncReport {
+  /// Remote changes applied (or to be applied) to the local database.
+  pub incoming: Vec<RecordChange>,
+  /// Local changes sent (or to be sent) to the remote.
+  pub outgoing: Vec<RecordChange>,
+}
+
+impl SyncReport {
+  pub fn is_empty(&self) -> bool {
+    self.incoming.is_empty() && self.outgoing.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatus {
+  pub remote: Option<String>,
+  /// Local commits (or changed files) the remote doesn't have yet.
+  pub ahead: usize,
+  /// Remote commits (or changed files) this device doesn't have yet.
+  pub behind: usize,
+  /// Descriptions of the local changes which aren't pushed yet.
+  pub pending: Vec<String>,
+  pub last_sync: Option<chrono::DateTime<chrono::Local>>,
+}
+
+/// Compares two versions of a database file, `base` is the older one.
+pub fn diff_database_file(filename: &str, base: &[u8], new: &[u8]) -> Vec<RecordChange> {
+  let collection = filename.trim_end_matches(".json");
+  let base_records = parse_records(base);
+  let new_records = parse_records(new);
+
+  let base_by_id: HashMap<uuid::Uuid, &serde_json::Value> = base_records
+    .iter()
+    .filter_map(|record| Some((record_id(record)?, record)))
+    .collect();
+  let new_ids: Vec<uuid::Uuid> = new_records.iter().filter_map(record_id).collect();
+
+  let mut changes = Vec::new();
+  for record in new_records.iter() {
+    let Some(id) = record_id(record) else {
+      continue;
+    };
+    let kind = match base_by_id.get(&id) {
+      None => ChangeKind::Added,
+      Some(&base_record) if base_record != record => ChangeKind::Changed,
+      Some(_) => continue,
+    };
+    changes.push(new_change(kind, collection, id, record));
+  }
+
+  for record in base_records.iter() {
+    if let Some(id) = record_id(record) {
+      if !new_ids.contains(&id) {
+        changes.push(new_change(ChangeKind::Removed, collection, id, record));
+      }
+    }
+  }
+
+  return changes;
+}
+
+pub(crate) fn parse_records(content: &[u8]) -> Vec<serde_json::Value> {
+  serde_json::from_slice(content).unwrap_or_default()
+}
+
+pub(crate) fn record_id(record: &serde_json::Value) -> Option<uuid::Uuid> {
+  record.get("id")?.as_str()?.parse().ok()
+}
+
+fn new_change(
+  kind: ChangeKind,
+  collection: &str,
+  id: uuid::Uuid,
+  record: &serde_json::Value,
+) -> RecordChange {
+  let title = record
+    .get("title")
+    .or(record.get("name"))
+    .and_then(|title| title.as_str())
+    .unwrap_or_default();
+
+  RecordChange {
+    kind,
+    collection: collection.to_owned(),
+    id,
+    title: title.to_owned(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{diff_database_file, ChangeKind};
+
+  #[test]
+  fn test_diff_database_file() {
+    let base = br#"[
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e001", "title": "kept"},
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e002", "title": "renamed"},
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e003", "title": "removed"}
+    ]"#;
+    let new = br#"[
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e001", "title": "kept"},
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e002", "title": "renamed!"},
+      {"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e004", "title": "added"}
+    ]"#;
+
+    let changes = diff_database_file("tasks.json", base, new);
+    let summary: Vec<(ChangeKind, &str)> = changes
+      .iter()
+      .map(|change| (change.kind, change.title.as_str()))
+      .collect();
+
+    assert_eq!(
+      summary,
+      vec![
+        (ChangeKind::Changed, "renamed!"),
+        (ChangeKind::Added, "added"),
+        (ChangeKind::Removed, "removed"),
+      ]
+    );
+    assert!(changes.iter().all(|change| change.collection == "tasks"));
+  }
+
+  #[test]
+  fn test_diff_database_file_from_empty() {
+    let new = br#"[{"id": "7d4bd1b0-8c2a-4b8e-9a4f-2f1a55a0e001", "name": "project"}]"#;
+
+    let changes = diff_database_file("projects.json", b"", new);
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].kind, ChangeKind::Added);
+    assert_eq!(changes[0].title, "project");
+  }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+
+use super::protocol::{RecordDelta, SyncRequest, SyncResponse};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct StoredRecord {
+  revision: u64,
+  device_id: uuid::Uuid,
+  delta: RecordDelta,
+}
+
+/// One database kept by `busy-server`: the latest version of every record
+/// (removed ones are kept as tombstones) stamped with the server revision it
+/// was written at. Revisions only grow, so a revision works as a sync cursor.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ServerDatabase {
+  revision: u64,
+  records: Vec<StoredRecord>,
+}
+
+impl ServerDatabase {
+  pub fn restore(filepath: &std::path::Path) -> std::io::Result<Self> {
+    match std::fs::read(filepath) {
+      Ok(content) => Ok(serde_json::from_slice(&content)?),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(err) => Err(err),
+    }
+  }
+
+  pub fn save(&self, filepath: &std::path::Path) -> std::io::Result<()> {
+    let tmp_filepath = filepath.with_extension("json.tmp");
+    std::fs::write(&tmp_filepath, serde_json::to_vec(self)?)?;
+    std::fs::rename(tmp_filepath, filepath)
+  }
+
+  /// Applies the device changes and returns every record changed by others
+  /// after the device cursor. When two devices changed the same record the
+  /// one with the later `updated_at` wins, the loser gets the winner back.
+  pub fn sync(&mut self, request: &SyncRequest) -> SyncResponse {
+    let mut accepted = HashSet::new();
+    let mut rejected = HashSet::new();
+
+    if request.replace && !request.dry_run {
+      let kept: HashSet<(&str, uuid::Uuid)> = request
+        .changes
+        .iter()
+        .map(|change| (change.collection.as_str(), change.id))
+        .collect();
+      let now = chrono::Local::now().fixed_offset();
+      let removed: Vec<RecordDelta> = self
+        .records
+        .iter()
+        .filter(|stored| stored.delta.record.is_some())
+        .filter(|stored| !kept.contains(&(stored.delta.collection.as_str(), stored.delta.id)))
+        .map(|stored| RecordDelta {
+          record: None,
+          deleted_at: Some(now),
+          ..stored.delta.clone()
+        })
+        .collect();
+      for delta in removed {
+        self.write(request.device_id, delta);
+      }
+    }
+
+    for change in request.changes.iter() {
+      let key = (change.collection.clone(), change.id);
+      let is_outdated = match self.position(&change.collection, change.id) {
+        Some(position) => {
+          let stored = &self.records[position].delta;
+          !request.replace && changed_at(stored) > changed_at(change)
+        }
+        None => false,
+      };
+
+      if is_outdated {
+        rejected.insert(key);
+        continue;
+      }
+      if !request.dry_run {
+        self.write(request.device_id, change.clone());
+      }
+      accepted.insert(key);
+    }
+
+    let changes = self
+      .records
+      .iter()
+      .filter(|stored| {
+        let key = (stored.delta.collection.clone(), stored.delta.id);
+        (stored.revision > request.cursor || rejected.contains(&key)) && !accepted.contains(&key)
+      })
+      .map(|stored| stored.delta.clone())
+      .collect();
+
+    SyncResponse {
+      cursor: self.revision,
+      changes,
+    }
+  }
+
+  fn position(&self, collection: &str, id: uuid::Uuid) -> Option<usize> {
+    self
+      .records
+      .iter()
+      .position(|stored| stored.delta.id == id && stored.delta.collection == collection)
+  }
+
+  fn write(&mut self, device_id: uuid::Uuid, delta: RecordDelta) {
+    self.revision += 1;
+    let stored = StoredRecord {
+      revision: self.revision,
+      device_id,
+      delta,
+    };
+    match self.position(&stored.delta.collection, stored.delta.id) {
+      Some(position) => self.records[position] = stored,
+      None => self.records.push(stored),
+    }
+  }
+}
+
+/// `updated_at` of the record, or `deleted_at` of a tombstone.
+fn changed_at(delta: &RecordDelta) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+  let Some(record) = delta.record.as_ref() else {
+    return delta.deleted_at;
+  };
+  let updated_at = record.get("updated_at")?.as_str()?;
+  chrono::DateTime::parse_from_rfc3339(updated_at).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ServerDatabase;
+  use crate::sync::protocol::{RecordDelta, SyncRequest};
+
+  fn task_delta(id: uuid::Uuid, title: &str, updated_at: &str) -> RecordDelta {
+    RecordDelta {
+      collection: "tasks".to_string(),
+      id,
+      record: Some(serde_json::json!({
+        "id": id,
+        "title": title,
+        "updated_at": updated_at,
+      })),
+      deleted_at: None,
+    }
+  }
+
+  fn deletion(id: uuid::Uuid, deleted_at: &str) -> RecordDelta {
+    RecordDelta {
+      collection: "tasks".to_string(),
+      id,
+      record: None,
+      deleted_at: Some(chrono::DateTime::parse_from_rfc3339(deleted_at).unwrap()),
+    }
+  }
+
+  fn request(cursor: u64, changes: Vec<RecordDelta>) -> SyncRequest {
+    SyncRequest {
+      device_id: uuid::Uuid::new_v4(),
+      cursor,
+      changes,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_sync_returns_changes_after_cursor() {
+    let mut database = ServerDatabase::default();
+    let first_id = uuid::Uuid::new_v4();
+    let second_id = uuid::Uuid::new_v4();
+
+    let first = database.sync(&request(0, vec![task_delta(first_id, "first", "2026-01-01T10:00:00Z")]));
+    assert!(first.changes.is_empty());
+    assert_eq!(first.cursor, 1);
+
+    database.sync(&request(0, vec![task_delta(second_id, "second", "2026-01-01T11:00:00Z")]));
+
+    let response = database.sync(&request(first.cursor, vec![]));
+    assert_eq!(response.cursor, 2);
+    assert_eq!(response.changes.len(), 1);
+    assert_eq!(response.changes[0].id, second_id);
+  }
+
+  #[test]
+  fn test_sync_keeps_latest_update() {
+    let mut database = ServerDatabase::default();
+    let id = uuid::Uuid::new_v4();
+    database.sync(&request(0, vec![task_delta(id, "newer", "2026-01-01T12:00:00Z")]));
+
+    let response = database.sync(&request(1, vec![task_delta(id, "older", "2026-01-01T11:00:00Z")]));
+
+    assert_eq!(response.cursor, 1);
+    assert_eq!(response.changes, vec![task_delta(id, "newer", "2026-01-01T12:00:00Z")]);
+  }
+
+  #[test]
+  fn test_sync_accepts_deletion_and_sends_it_to_others() {
+    let mut database = ServerDatabase::default();
+    let id = uuid::Uuid::new_v4();
+    database.sync(&request(0, vec![task_delta(id, "task", "2026-01-01T10:00:00Z")]));
+
+    let response = database.sync(&request(1, vec![deletion(id, "2026-01-01T11:00:00Z")]));
+    assert_eq!(response.cursor, 2);
+    assert!(response.changes.is_empty());
+
+    let other_device = database.sync(&request(1, vec![]));
+    assert_eq!(other_device.changes, vec![deletion(id, "2026-01-01T11:00:00Z")]);
+  }
+
+  #[test]
+  fn test_sync_rejects_stale_edit_after_deletion() {
+    let mut database = ServerDatabase::default();
+    let id = uuid::Uuid::new_v4();
+    database.sync(&request(0, vec![task_delta(id, "task", "2026-01-01T10:00:00Z")]));
+    database.sync(&request(1, vec![deletion(id, "2026-01-01T12:00:00Z")]));
+
+    let response = database.sync(&request(1, vec![task_delta(id, "edited", "2026-01-01T11:00:00Z")]));
+
+    assert_eq!(response.cursor, 2);
+    assert_eq!(response.changes, vec![deletion(id, "2026-01-01T12:00:00Z")]);
+
+    let newer = database.sync(&request(2, vec![task_delta(id, "restored", "2026-01-01T13:00:00Z")]));
+    assert_eq!(newer.cursor, 3);
+    assert!(newer.changes.is_empty());
+  }
+
+  #[test]
+  fn test_sync_dry_run_doesnt_apply_changes() {
+    let mut database = ServerDatabase::default();
+    let mut dry_run = request(0, vec![task_delta(uuid::Uuid::new_v4(), "task", "2026-01-01T12:00:00Z")]);
+    dry_run.dry_run = true;
+
+    let response = database.sync(&dry_run);
+
+    assert_eq!(response.cursor, 0);
+    assert!(database.records.is_empty());
+  }
+}
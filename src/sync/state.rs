@@ -0,0 +1,64 @@
+/// Local bookkeeping of the syncer which isn't synced itself: when the last
+/// sync happened and how many changes are queued for the next one.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SyncState {
+  pub last_sync: Option<chrono::DateTime<chrono::Local>>,
+  pub last_attempt: Option<chrono::DateTime<chrono::Local>>,
+  pub pending_changes: usize,
+  #[serde(skip)]
+  filepath: std::path::PathBuf,
+}
+
+impl SyncState {
+  pub const FILENAME: &'static str = ".sync_state.json";
+
+  pub fn restore(main_folder_path: &str) -> Self {
+    let filepath = std::path::Path::new(main_folder_path).join(Self::FILENAME);
+    let mut state: Self = std::fs::read(&filepath)
+      .ok()
+      .and_then(|content| serde_json::from_slice(&content).ok())
+      .unwrap_or_default();
+    state.filepath = filepath;
+    return state;
+  }
+
+  pub fn save(&self) {
+    let content = serde_json::to_string_pretty(self).unwrap();
+    if let Err(err) = std::fs::write(&self.filepath, content) {
+      log::debug!("can't save sync state to {:?}: {err}", self.filepath);
+    }
+  }
+
+  pub fn record_change(&mut self) {
+    self.pending_changes += 1;
+    self.save();
+  }
+
+  pub fn record_attempt_started(&mut self) {
+    self.last_attempt = Some(chrono::Local::now());
+    self.save();
+  }
+
+  pub fn record_attempt(&mut self, succeeded: bool) {
+    let now = chrono::Local::now();
+    self.last_attempt = Some(now);
+    if succeeded {
+      self.last_sync = Some(now);
+      self.pending_changes = 0;
+    }
+    self.save();
+  }
+
+  pub fn minutes_since_last_attempt(&self) -> Option<i64> {
+    self
+      .last_attempt
+      .map(|attempt| (chrono::Local::now() - attempt).num_minutes())
+  }
+
+  pub fn attempted_today(&self) -> bool {
+    match self.last_attempt {
+      Some(attempt) => attempt.date_naive() == chrono::Local::now().date_naive(),
+      None => false,
+    }
+  }
+}
@@ -1,6 +1,22 @@
+use crate::history::TaskRevision;
+
+use super::report::{SyncReport, SyncStatus};
+
 pub trait Syncer {
   fn commit(&mut self, msg: &str) -> std::io::Result<String>;
-  fn sync(&mut self) -> std::io::Result<String>;
+  fn sync(&mut self) -> std::io::Result<SyncReport>;
+  /// Fetches the remote state and reports what `sync` would exchange without
+  /// changing anything.
+  fn dry_run(&mut self) -> std::io::Result<SyncReport>;
+  fn status(&mut self) -> std::io::Result<SyncStatus>;
+  /// Squashes the synced history older than `older_than` to keep the remote
+  /// small. Syncers which don't keep history have nothing to do.
+  fn compact(&mut self, older_than: chrono::DateTime<chrono::Local>) -> std::io::Result<String>;
   fn push_force(&mut self) -> std::io::Result<String>;
   fn pull_force(&mut self) -> std::io::Result<String>;
+  /// Whether the syncer keeps the history of changes itself, otherwise
+  /// `Busy` keeps a local change log.
+  fn keeps_history(&self) -> bool;
+  /// Revisions of the task from the oldest to the newest.
+  fn task_history(&mut self, task_id: uuid::Uuid) -> std::io::Result<Vec<TaskRevision>>;
 }
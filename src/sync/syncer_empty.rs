@@ -1,4 +1,9 @@
-use super::syncer::Syncer;
+use crate::history::TaskRevision;
+
+use super::{
+  report::{SyncReport, SyncStatus},
+  syncer::Syncer,
+};
 
 pub struct EmptySyncer {}
 
@@ -12,8 +17,17 @@ impl Syncer for EmptySyncer {
   fn commit(&mut self, msg: &str) -> std::io::Result<String> {
     return std::io::Result::Ok(format!("cmd: 'commit', msg: {msg}"));
   }
-  fn sync(&mut self) -> std::io::Result<String> {
-    return std::io::Result::Ok(format!("cmd: 'sync'"));
+  fn sync(&mut self) -> std::io::Result<SyncReport> {
+    return std::io::Result::Ok(SyncReport::default());
+  }
+  fn dry_run(&mut self) -> std::io::Result<SyncReport> {
+    return std::io::Result::Ok(SyncReport::default());
+  }
+  fn status(&mut self) -> std::io::Result<SyncStatus> {
+    return std::io::Result::Ok(SyncStatus::default());
+  }
+  fn compact(&mut self, _: chrono::DateTime<chrono::Local>) -> std::io::Result<String> {
+    return std::io::Result::Ok(format!("cmd: 'compact'"));
   }
   fn push_force(&mut self) -> std::io::Result<String> {
     return std::io::Result::Ok(format!("cmd: 'push_force'"));
@@ -21,4 +35,10 @@ impl Syncer for EmptySyncer {
   fn pull_force(&mut self) -> std::io::Result<String> {
     return std::io::Result::Ok(format!("cmd: 'pull_force'"));
   }
+  fn keeps_history(&self) -> bool {
+    return false;
+  }
+  fn task_history(&mut self, _: uuid::Uuid) -> std::io::Result<Vec<TaskRevision>> {
+    return std::io::Result::Ok(Vec::new());
+  }
 }
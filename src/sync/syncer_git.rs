@@ -1,10 +1,31 @@
-use super::syncer::Syncer;
+use std::io::Write;
+
+use crate::{
+  history::{ChangeLog, TaskRevision},
+  recurring::RecurringState,
+  storage::JsonStorage,
+};
+
+use super::{
+  report::{diff_database_file, parse_records, record_id, SyncReport, SyncStatus},
+  state::SyncState,
+  syncer::Syncer,
+};
+
+/// Messages of the changes which are batched into the next sync commit. Lives
+/// inside `.git`, so it's never committed itself.
+const BATCHED_MESSAGES_FILENAME: &str = "busy_batched_messages";
+/// The remote commit this device synced with last. Unlike the remote tracking
+/// branch it isn't moved by fetches of `sync --status` or `--dry-run`, so a
+/// rewritten remote history is still detected by the next sync.
+const LAST_SYNCED_REF: &str = "refs/busy/last-synced";
 
 pub struct GitSyncer {
   main_folder_path: String,
   key_file: Option<String>,
   remote: Option<String>,
   branch: String,
+  batch_commits: bool,
 }
 
 impl GitSyncer {
@@ -13,6 +34,7 @@ impl GitSyncer {
     key_file: Option<String>,
     remote: Option<String>,
     branch: Option<String>,
+    batch_commits: bool,
   ) -> Self {
     const DEFAULT_BRANCH: &str = "main";
 
@@ -21,6 +43,7 @@ impl GitSyncer {
       key_file,
       remote,
       branch: branch.unwrap_or(DEFAULT_BRANCH.to_owned()),
+      batch_commits,
     };
     _ = obj.init();
     return obj;
@@ -31,15 +54,189 @@ impl GitSyncer {
       .join(".git")
       .exists()
     {
+      self.exclude_local_files()?;
       return self.set_remote();
     }
     self.git_with_args(&["init", "-b", self.branch.clone().as_str()])?;
+    self.exclude_local_files()?;
     _ = self.set_remote();
     _ = self.pull();
 
     return Ok("initialization success".to_string());
   }
 
+  /// Keeps device-local files such as the sync state out of the commits.
+  fn exclude_local_files(&self) -> std::io::Result<()> {
+    let exclude_path = std::path::Path::new(&self.main_folder_path).join(".git/info/exclude");
+    let content = std::fs::read_to_string(&exclude_path).unwrap_or_default();
+    let missing: Vec<&str> = [SyncState::FILENAME, ChangeLog::FILENAME, RecurringState::FILENAME]
+      .into_iter()
+      .filter(|filename| !content.lines().any(|line| line == *filename))
+      .collect();
+    if missing.is_empty() {
+      return Ok(());
+    }
+
+    std::fs::create_dir_all(exclude_path.parent().unwrap())?;
+    let mut file = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(exclude_path)?;
+    for filename in missing {
+      writeln!(file, "{}", filename)?;
+    }
+    return Ok(());
+  }
+
+  fn batched_messages_path(&self) -> std::path::PathBuf {
+    std::path::Path::new(&self.main_folder_path)
+      .join(".git")
+      .join(BATCHED_MESSAGES_FILENAME)
+  }
+
+  fn batched_messages(&self) -> Vec<String> {
+    std::fs::read_to_string(self.batched_messages_path())
+      .unwrap_or_default()
+      .lines()
+      .map(|line| line.to_owned())
+      .collect()
+  }
+
+  fn batch_message(&self, msg: &str) -> std::io::Result<String> {
+    let mut file = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(self.batched_messages_path())?;
+    writeln!(file, "{}", msg.replace('\n', " "))?;
+    return Ok(format!("batched: {msg}"));
+  }
+
+  /// Commits all uncommitted changes, batched or not, as a single commit.
+  fn commit_batch(&mut self) -> std::io::Result<()> {
+    let messages = self.batched_messages();
+    let has_changes = !self
+      .git_with_args(&["status", "--porcelain"])?
+      .trim()
+      .is_empty();
+
+    if has_changes {
+      let title = match messages.len() {
+        0 => "sync local changes".to_owned(),
+        1 => messages[0].clone(),
+        count => format!("{count} changes"),
+      };
+      self.git_with_args(&["add", "-A"])?;
+      self.git_with_args(&["commit", "-m", &title, "-m", &messages.join("\n")])?;
+    }
+
+    match std::fs::remove_file(self.batched_messages_path()) {
+      Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+      _ => Ok(()),
+    }
+  }
+
+  fn rev_parse(&mut self, rev: &str) -> Option<String> {
+    self
+      .git_with_args(&["rev-parse", "--verify", "--quiet", rev])
+      .ok()
+      .map(|sha| sha.trim().to_owned())
+  }
+
+  fn remember_synced(&mut self) -> std::io::Result<()> {
+    let remote_ref = self.remote_ref();
+    if let Some(remote_sha) = self.rev_parse(&remote_ref) {
+      self.git_with_args(&["update-ref", LAST_SYNCED_REF, &remote_sha])?;
+    }
+    return Ok(());
+  }
+
+  /// Another device may have compacted the history since the last sync. In
+  /// that case the local commits are replayed on top of the new remote
+  /// history, otherwise the following pull would fail on unrelated histories.
+  fn rebase_if_remote_rewritten(&mut self, previous_remote_sha: Option<String>) -> std::io::Result<()> {
+    let remote_ref = self.remote_ref();
+    let (Some(previous_sha), Some(current_sha)) = (previous_remote_sha, self.rev_parse(&remote_ref))
+    else {
+      return Ok(());
+    };
+
+    let is_fast_forward = self
+      .git_with_args(&["merge-base", "--is-ancestor", &previous_sha, &current_sha])
+      .is_ok();
+    if is_fast_forward {
+      return Ok(());
+    }
+
+    log::debug!("remote history was rewritten: {previous_sha} -> {current_sha}");
+    let branch = self.branch.clone();
+    let rebase_result =
+      self.git_with_args(&["rebase", "--onto", &current_sha, &previous_sha, &branch]);
+    if let Err(err) = rebase_result {
+      _ = self.git_with_args(&["rebase", "--abort"]);
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("remote history was compacted and local changes can't be replayed on top of it:\n{err}"),
+      ));
+    }
+    return Ok(());
+  }
+
+  /// Replays the first-parent history after `boundary` on top of a new root
+  /// commit with the tree of `boundary`. Trees, messages and authors are kept,
+  /// so the database content at every remaining commit is unchanged.
+  fn squash_history_before(&mut self, boundary: &str) -> std::io::Result<String> {
+    let boundary_date = self.git_with_args(&["log", "-1", "--format=%cI", boundary])?;
+    let boundary_date = boundary_date.trim();
+    let mut new_head = git_with_args_and_env(
+      &self.main_folder_path,
+      self.key_file.as_ref(),
+      &[
+        "commit-tree",
+        &format!("{boundary}^{{tree}}"),
+        "-m",
+        &format!("compacted history before {boundary_date}"),
+      ],
+      &[
+        ("GIT_AUTHOR_DATE", boundary_date),
+        ("GIT_COMMITTER_DATE", boundary_date),
+      ],
+    )?;
+
+    let commits = self.git_with_args(&[
+      "rev-list",
+      "--reverse",
+      "--first-parent",
+      &format!("{boundary}..HEAD"),
+    ])?;
+    for commit in commits.lines() {
+      let info = self.git_with_args(&["log", "-1", "--format=%an%x00%ae%x00%aI%x00%cI%x00%B", commit])?;
+      let parts: Vec<&str> = info.splitn(5, '\0').collect();
+      new_head = git_with_args_and_env(
+        &self.main_folder_path,
+        self.key_file.as_ref(),
+        &[
+          "commit-tree",
+          &format!("{commit}^{{tree}}"),
+          "-p",
+          new_head.trim(),
+          "-m",
+          parts[4].trim(),
+        ],
+        &[
+          ("GIT_AUTHOR_NAME", parts[0]),
+          ("GIT_AUTHOR_EMAIL", parts[1]),
+          ("GIT_AUTHOR_DATE", parts[2]),
+          ("GIT_COMMITTER_DATE", parts[3]),
+        ],
+      )?;
+    }
+
+    let new_head = new_head.trim().to_owned();
+    let branch_ref = format!("refs/heads/{}", self.branch);
+    self.git_with_args(&["update-ref", &branch_ref, &new_head])?;
+    return Ok(new_head);
+  }
+
   fn push(&mut self) -> std::io::Result<String> {
     return self.git_with_args(&["push", "-u", "origin", self.branch.clone().as_str()]);
   }
@@ -48,6 +245,97 @@ impl GitSyncer {
     return self.git_with_args(&["pull", "origin", self.branch.clone().as_str()]);
   }
 
+  /// Updates the remote tracking branch. Returns `false` when the remote
+  /// branch doesn't exist yet, e.g. nothing was pushed to a new remote.
+  fn fetch(&mut self) -> std::io::Result<bool> {
+    let fetch_result = self.git_with_args(&["fetch", "origin", self.branch.clone().as_str()]);
+    let has_remote_ref = self.has_remote_ref();
+    match fetch_result {
+      Err(err) if has_remote_ref => Err(err),
+      _ => Ok(has_remote_ref),
+    }
+  }
+
+  fn remote_ref(&self) -> String {
+    format!("origin/{}", self.branch)
+  }
+
+  fn has_remote_ref(&mut self) -> bool {
+    let remote_ref = self.remote_ref();
+    self
+      .git_with_args(&["rev-parse", "--verify", "--quiet", remote_ref.as_str()])
+      .is_ok()
+  }
+
+  fn file_at(&mut self, rev: &str, filename: &str) -> String {
+    self
+      .git_with_args(&["show", format!("{rev}:{filename}").as_str()])
+      .unwrap_or_default()
+  }
+
+  /// The task as it's stored in `tasks_content`, `None` if it isn't there.
+  fn task_revision(
+    task_id: uuid::Uuid,
+    tasks_content: &[u8],
+    time: chrono::DateTime<chrono::Local>,
+    message: &str,
+  ) -> TaskRevision {
+    let task = parse_records(tasks_content)
+      .into_iter()
+      .find(|record| record_id(record) == Some(task_id));
+    let updated_at = task
+      .as_ref()
+      .and_then(|task| task.get("updated_at")?.as_str()?.parse().ok());
+
+    TaskRevision {
+      time: updated_at.unwrap_or(time),
+      task_id,
+      message: message.to_owned(),
+      task,
+    }
+  }
+
+  fn count_commits(&mut self, range: &str) -> usize {
+    self
+      .git_with_args(&["rev-list", "--count", range])
+      .map(|count| count.trim().parse().unwrap_or_default())
+      .unwrap_or_default()
+  }
+
+  /// Diffs the working tree and the fetched remote branch against their
+  /// merge base. Expects the remote to be fetched already.
+  fn report_since_merge_base(&mut self, has_remote_ref: bool) -> SyncReport {
+    let remote_ref = self.remote_ref();
+    let merge_base = match has_remote_ref {
+      true => self
+        .git_with_args(&["merge-base", "HEAD", remote_ref.as_str()])
+        .ok()
+        .map(|rev| rev.trim().to_owned()),
+      false => None,
+    };
+
+    let mut report = SyncReport::default();
+    for filename in JsonStorage::FILENAMES {
+      let base = match merge_base.as_ref() {
+        Some(rev) => self.file_at(rev, filename),
+        None => String::new(),
+      };
+      let local =
+        std::fs::read(std::path::Path::new(&self.main_folder_path).join(filename)).unwrap_or_default();
+      report
+        .outgoing
+        .extend(diff_database_file(filename, base.as_bytes(), &local));
+
+      if has_remote_ref {
+        let remote = self.file_at(&remote_ref, filename);
+        report
+          .incoming
+          .extend(diff_database_file(filename, base.as_bytes(), remote.as_bytes()));
+      }
+    }
+    return report;
+  }
+
   fn set_remote(&mut self) -> std::io::Result<String> {
     if self.remote.is_some() {
       return match self.set_remote_url() {
@@ -79,45 +367,200 @@ impl GitSyncer {
 
 impl Syncer for GitSyncer {
   fn commit(&mut self, msg: &str) -> std::io::Result<String> {
+    if self.batch_commits {
+      return self.batch_message(msg);
+    }
     self.git_with_args(&["add", "-A"])?;
     return self.git_with_args(&["commit", "-a", "-m", msg]);
   }
 
-  fn sync(&mut self) -> std::io::Result<String> {
-    let pull_output = self.pull()?;
-    let push_output = self.push()?;
-    return Ok(format!(
-      "git pull output:\n{}\n\ngit push output:\n{}",
-      pull_output, push_output
-    ));
+  fn sync(&mut self) -> std::io::Result<SyncReport> {
+    self.commit_batch()?;
+    let remote_ref = self.remote_ref();
+    let previous_remote_sha = self
+      .rev_parse(LAST_SYNCED_REF)
+      .or_else(|| self.rev_parse(&remote_ref));
+    let has_remote_ref = self.fetch()?;
+    self.rebase_if_remote_rewritten(previous_remote_sha)?;
+    let report = self.report_since_merge_base(has_remote_ref);
+    if has_remote_ref {
+      self.pull()?;
+    }
+    self.push()?;
+    self.remember_synced()?;
+    return Ok(report);
+  }
+
+  fn dry_run(&mut self) -> std::io::Result<SyncReport> {
+    let has_remote_ref = self.fetch()?;
+    return Ok(self.report_since_merge_base(has_remote_ref));
+  }
+
+  fn status(&mut self) -> std::io::Result<SyncStatus> {
+    let has_remote_ref = self.fetch()?;
+    let remote_ref = self.remote_ref();
+    let pending_range = match has_remote_ref {
+      true => format!("{remote_ref}..HEAD"),
+      false => "HEAD".to_owned(),
+    };
+
+    let mut pending = self.batched_messages();
+    let batched_count = pending.len();
+    pending.extend(
+      self
+        .git_with_args(&["log", "--format=%s", pending_range.as_str()])
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.to_owned()),
+    );
+
+    return Ok(SyncStatus {
+      remote: self.remote.clone(),
+      ahead: batched_count + self.count_commits(&pending_range),
+      behind: match has_remote_ref {
+        true => self.count_commits(&format!("HEAD..{remote_ref}")),
+        false => 0,
+      },
+      pending,
+      last_sync: None,
+    });
+  }
+
+  fn compact(&mut self, older_than: chrono::DateTime<chrono::Local>) -> std::io::Result<String> {
+    self.sync()?;
+
+    let remote_ref = self.remote_ref();
+    let remote_sha = self.rev_parse(&remote_ref).unwrap_or_default();
+    let boundary = self
+      .git_with_args(&[
+        "rev-list",
+        "-1",
+        "--first-parent",
+        &format!("--before={}", older_than.to_rfc3339()),
+        "HEAD",
+      ])?
+      .trim()
+      .to_owned();
+
+    let has_parent = self.rev_parse(&format!("{boundary}^")).is_some();
+    if boundary.is_empty() || !has_parent {
+      return Ok("nothing to compact".to_string());
+    }
+
+    let removed_count = self.count_commits(&boundary);
+    self.squash_history_before(&boundary)?;
+
+    let branch = self.branch.clone();
+    self.git_with_args(&[
+      "push",
+      &format!("--force-with-lease={branch}:{remote_sha}"),
+      "-u",
+      "origin",
+      &branch,
+    ])?;
+    self.remember_synced()?;
+    _ = self.git_with_args(&["gc", "--prune=now", "--quiet"]);
+
+    return Ok(format!("{removed_count} commits squashed into one"));
   }
 
   fn push_force(&mut self) -> std::io::Result<String> {
-    return self.git_with_args(&[
+    self.commit_batch()?;
+    let output = self.git_with_args(&[
       "push",
       "--force",
       "-u",
       "origin",
       self.branch.clone().as_str(),
-    ]);
+    ])?;
+    self.remember_synced()?;
+    return Ok(output);
   }
 
   fn pull_force(&mut self) -> std::io::Result<String> {
-    return self.git_with_args(&[
+    self.commit_batch()?;
+    let output = self.git_with_args(&[
       "pull",
       "--force",
       "--rebase",
       "origin",
       self.branch.clone().as_str(),
-    ]);
+    ])?;
+    self.remember_synced()?;
+    return Ok(output);
+  }
+
+  fn keeps_history(&self) -> bool {
+    return true;
+  }
+
+  /// Every task commit mentions the task id, so the history is the commits
+  /// found by the id plus the batched changes which aren't committed yet.
+  fn task_history(&mut self, task_id: uuid::Uuid) -> std::io::Result<Vec<TaskRevision>> {
+    let id = task_id.to_string();
+    let log = self
+      .git_with_args(&[
+        "log",
+        "--reverse",
+        "--fixed-strings",
+        &format!("--grep={id}"),
+        "--format=%H%x1f%aI%x1f%s",
+        "--",
+        JsonStorage::TASKS_FILENAME,
+      ])
+      .unwrap_or_default();
+
+    let mut revisions = Vec::new();
+    for line in log.lines() {
+      let mut parts = line.splitn(3, '\x1f');
+      let (Some(sha), Some(time), Some(message)) = (parts.next(), parts.next(), parts.next()) else {
+        continue;
+      };
+      let Ok(time) = chrono::DateTime::parse_from_rfc3339(time) else {
+        continue;
+      };
+      // Task commits look like `<action> task title: ...`, keep the action.
+      let action = message.split(" task title: ").next().unwrap_or(message);
+      let content = self.file_at(sha, JsonStorage::TASKS_FILENAME);
+      revisions.push(Self::task_revision(
+        task_id,
+        content.as_bytes(),
+        time.with_timezone(&chrono::Local),
+        action,
+      ));
+    }
+
+    if self.batched_messages().iter().any(|message| message.contains(&id)) {
+      let content = std::fs::read(
+        std::path::Path::new(&self.main_folder_path).join(JsonStorage::TASKS_FILENAME),
+      )
+      .unwrap_or_default();
+      revisions.push(Self::task_revision(
+        task_id,
+        &content,
+        chrono::Local::now(),
+        "not synced yet",
+      ));
+    }
+    return Ok(revisions);
   }
 }
 
 fn git_with_args(cwd: &str, key_file: Option<&String>, args: &[&str]) -> std::io::Result<String> {
+  return git_with_args_and_env(cwd, key_file, args, &[]);
+}
+
+fn git_with_args_and_env(
+  cwd: &str,
+  key_file: Option<&String>,
+  args: &[&str],
+  envs: &[(&str, &str)],
+) -> std::io::Result<String> {
   log::debug!("run git with args: {args:?} cwd: {cwd} key_file: {key_file:?}");
 
   let mut command = std::process::Command::new("git");
   command.current_dir(cwd);
+  command.envs(envs.iter().copied());
   if key_file.is_some() {
     command.env("GIT_SSH_COMMAND", format!("ssh -i {}", key_file.unwrap()));
   }
@@ -125,11 +568,66 @@ fn git_with_args(cwd: &str, key_file: Option<&String>, args: &[&str]) -> std::io
 
   let stdout = String::from_utf8(output.stdout.clone()).unwrap_or_default();
   if !output.status.success() {
-    log::debug!("git with err: {} status: {}", stdout, output.status);
-    return Err(std::io::Error::new(std::io::ErrorKind::Other, stdout));
+    let stderr = String::from_utf8(output.stderr.clone()).unwrap_or_default();
+    log::debug!("git with err: {} {} status: {}", stdout, stderr, output.status);
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::Other,
+      format!("{stdout}{stderr}"),
+    ));
   }
 
   log::debug!("git with output: {:?} status: {}", stdout, output.status);
 
   return Ok(stdout);
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{git_with_args, GitSyncer};
+  use crate::sync::syncer::Syncer;
+
+  fn clone_device(root: &std::path::Path, name: &str, remote: &str) -> (String, GitSyncer) {
+    let path = root.join(name).to_str().unwrap().to_owned();
+    std::fs::create_dir_all(&path).unwrap();
+    let syncer = GitSyncer::new(&path, None, Some(remote.to_owned()), None, false);
+    git_with_args(&path, None, &["config", "user.name", name]).unwrap();
+    git_with_args(&path, None, &["config", "user.email", "busy@example.com"]).unwrap();
+    return (path, syncer);
+  }
+
+  fn change(syncer: &mut GitSyncer, path: &str, filename: &str, content: &str) {
+    std::fs::write(std::path::Path::new(path).join(filename), content).unwrap();
+    syncer.commit(&format!("change {filename}")).unwrap();
+  }
+
+  #[test]
+  fn test_sync_after_remote_compacted_and_status_fetched() {
+    let root = std::env::temp_dir().join(format!("busy-git-test-{}", uuid::Uuid::new_v4()));
+    let remote = root.join("remote.git").to_str().unwrap().to_owned();
+    std::fs::create_dir_all(&remote).unwrap();
+    git_with_args(&remote, None, &["init", "--bare", "-b", "main"]).unwrap();
+
+    let (a_path, mut a) = clone_device(&root, "a", &remote);
+    change(&mut a, &a_path, "tasks.json", "[1]");
+    change(&mut a, &a_path, "tasks.json", "[1, 2]");
+    a.sync().unwrap();
+
+    let (b_path, mut b) = clone_device(&root, "b", &remote);
+    b.sync().unwrap();
+    change(&mut b, &b_path, "tags.json", "[3]");
+
+    let compacted = a.compact(chrono::Local::now() + chrono::Duration::minutes(1));
+    assert_eq!(compacted.unwrap(), "2 commits squashed into one");
+
+    b.status().unwrap();
+    b.sync().unwrap();
+
+    let remote_log = git_with_args(&remote, None, &["log", "--format=%s", "main"]).unwrap();
+    assert_eq!(remote_log.lines().count(), 2);
+    assert!(remote_log.starts_with("change tags.json"));
+    let tasks = std::fs::read_to_string(std::path::Path::new(&b_path).join("tasks.json")).unwrap();
+    assert_eq!(tasks, "[1, 2]");
+
+    _ = std::fs::remove_dir_all(root);
+  }
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use crate::{history::TaskRevision, storage::JsonStorage};
+
+use super::{
+  protocol::{sync_path, RecordDelta, SyncRequest, SyncResponse},
+  report::{diff_database_file, parse_records, record_id, SyncReport, SyncStatus},
+  syncer::Syncer,
+};
+
+const STATE_FILENAME: &str = ".http_sync_state.json";
+/// Copies of the database files as of the last sync, local changes are the
+/// records which differ from them.
+const BASE_FOLDER: &str = ".http_sync_base";
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct HttpSyncState {
+  cursor: u64,
+}
+
+/// Syncs record by record with `busy-server`: sends the records changed
+/// since the last sync and receives the ones changed by other devices.
+pub struct HttpSyncer {
+  main_folder_path: String,
+  url: String,
+  database: String,
+  token: Option<String>,
+  device_id: uuid::Uuid,
+  state: HttpSyncState,
+}
+
+impl HttpSyncer {
+  pub fn new(
+    main_folder_path: &str,
+    url: &str,
+    database: &str,
+    token: Option<String>,
+    device_id: uuid::Uuid,
+  ) -> Self {
+    let mut obj = Self {
+      main_folder_path: main_folder_path.to_owned(),
+      url: url.trim_end_matches('/').to_owned(),
+      database: database.to_owned(),
+      token,
+      device_id,
+      state: HttpSyncState::default(),
+    };
+    obj.restore_state();
+    return obj;
+  }
+
+  fn local_path(&self, filename: &str) -> std::path::PathBuf {
+    std::path::Path::new(&self.main_folder_path).join(filename)
+  }
+
+  fn read_local(&self, filename: &str) -> Vec<u8> {
+    std::fs::read(self.local_path(filename)).unwrap_or_default()
+  }
+
+  fn read_base(&self, filename: &str) -> Vec<u8> {
+    std::fs::read(self.local_path(BASE_FOLDER).join(filename)).unwrap_or_default()
+  }
+
+  fn restore_state(&mut self) {
+    if let Ok(content) = std::fs::read(self.local_path(STATE_FILENAME)) {
+      self.state = serde_json::from_slice(&content).unwrap_or_default();
+    }
+  }
+
+  /// Saves the new cursor and the current database files as the new base.
+  fn remember(&mut self, cursor: u64) -> std::io::Result<()> {
+    let base_folder = self.local_path(BASE_FOLDER);
+    std::fs::create_dir_all(&base_folder)?;
+    for filename in JsonStorage::FILENAMES {
+      std::fs::write(base_folder.join(filename), self.read_local(filename))?;
+    }
+
+    self.state.cursor = cursor;
+    std::fs::write(
+      self.local_path(STATE_FILENAME),
+      serde_json::to_vec_pretty(&self.state)?,
+    )
+  }
+
+  fn local_changes(&self) -> (Vec<RecordDelta>, SyncReport) {
+    let mut deltas = Vec::new();
+    let mut report = SyncReport::default();
+
+    for filename in JsonStorage::FILENAMES {
+      let collection = filename.trim_end_matches(".json");
+      let base = self.read_base(filename);
+      let local = self.read_local(filename);
+      let local_records: HashMap<uuid::Uuid, serde_json::Value> = parse_records(&local)
+        .into_iter()
+        .filter_map(|record| Some((record_id(&record)?, record)))
+        .collect();
+
+      for change in diff_database_file(filename, &base, &local) {
+        let record = local_records.get(&change.id).cloned();
+        deltas.push(RecordDelta {
+          collection: collection.to_owned(),
+          id: change.id,
+          deleted_at: record.is_none().then(|| chrono::Local::now().fixed_offset()),
+          record,
+        });
+        report.outgoing.push(change);
+      }
+    }
+    return (deltas, report);
+  }
+
+  fn all_local_records(&self) -> Vec<RecordDelta> {
+    let mut deltas = Vec::new();
+    for filename in JsonStorage::FILENAMES {
+      for record in parse_records(&self.read_local(filename)) {
+        if let Some(id) = record_id(&record) {
+          deltas.push(RecordDelta {
+            collection: filename.trim_end_matches(".json").to_owned(),
+            id,
+            record: Some(record),
+            deleted_at: None,
+          });
+        }
+      }
+    }
+    return deltas;
+  }
+
+  /// Applies the received records to the database files. With `replace` the
+  /// files are rebuilt from the received records only, with `dry_run` they
+  /// aren't written at all. Returns what was (or would be) changed.
+  fn apply(
+    &self,
+    changes: &[RecordDelta],
+    replace: bool,
+    dry_run: bool,
+  ) -> std::io::Result<SyncReport> {
+    let mut report = SyncReport::default();
+
+    for filename in JsonStorage::FILENAMES {
+      let collection = filename.trim_end_matches(".json");
+      let local = self.read_local(filename);
+      let mut records = match replace {
+        true => Vec::new(),
+        false => parse_records(&local),
+      };
+
+      for change in changes.iter().filter(|c| c.collection == collection) {
+        let position = records
+          .iter()
+          .position(|record| record_id(record) == Some(change.id));
+        match (position, change.record.as_ref()) {
+          (Some(position), Some(record)) => records[position] = record.clone(),
+          (None, Some(record)) => records.push(record.clone()),
+          (Some(position), None) => _ = records.remove(position),
+          (None, None) => {}
+        }
+      }
+
+      let new_content = serde_json::to_vec_pretty(&records)?;
+      let changed = diff_database_file(filename, &local, &new_content);
+      if !changed.is_empty() && !dry_run {
+        std::fs::write(self.local_path(filename), &new_content)?;
+      }
+      report.incoming.extend(changed);
+    }
+    return Ok(report);
+  }
+
+  fn send(&self, request: &SyncRequest) -> std::io::Result<SyncResponse> {
+    let url = format!("{}{}", self.url, sync_path(&self.database));
+    let mut http_request = ureq::post(&url).set("Content-Type", "application/json");
+    if let Some(token) = self.token.as_ref() {
+      http_request = http_request.set("Authorization", &format!("Bearer {token}"));
+    }
+
+    let body = serde_json::to_vec(request)?;
+    let response = http_request.send_bytes(&body).map_err(|err| {
+      let message = match err {
+        ureq::Error::Status(code, response) => format!(
+          "busy server responded with {code}: {}",
+          response.into_string().unwrap_or_default()
+        ),
+        ureq::Error::Transport(transport) => format!("busy server request failed: {transport}"),
+      };
+      std::io::Error::new(std::io::ErrorKind::Other, message)
+    })?;
+    return Ok(serde_json::from_reader(response.into_reader())?);
+  }
+
+  fn new_request(&self, changes: Vec<RecordDelta>) -> SyncRequest {
+    SyncRequest {
+      device_id: self.device_id,
+      cursor: self.state.cursor,
+      changes,
+      ..Default::default()
+    }
+  }
+}
+
+impl Syncer for HttpSyncer {
+  fn commit(&mut self, msg: &str) -> std::io::Result<String> {
+    return Ok(format!("cmd: 'commit', msg: {msg}"));
+  }
+
+  fn sync(&mut self) -> std::io::Result<SyncReport> {
+    let (deltas, mut report) = self.local_changes();
+    let response = self.send(&self.new_request(deltas))?;
+
+    report.incoming = self.apply(&response.changes, false, false)?.incoming;
+    self.remember(response.cursor)?;
+    return Ok(report);
+  }
+
+  fn dry_run(&mut self) -> std::io::Result<SyncReport> {
+    let (deltas, mut report) = self.local_changes();
+    let mut request = self.new_request(deltas);
+    request.dry_run = true;
+    let response = self.send(&request)?;
+
+    report.incoming = self.apply(&response.changes, false, true)?.incoming;
+    return Ok(report);
+  }
+
+  fn status(&mut self) -> std::io::Result<SyncStatus> {
+    let (_, report) = self.local_changes();
+    let mut request = self.new_request(Vec::new());
+    request.dry_run = true;
+    let response = self.send(&request)?;
+
+    return Ok(SyncStatus {
+      remote: Some(format!("{}{}", self.url, sync_path(&self.database))),
+      ahead: report.outgoing.len(),
+      behind: response.changes.len(),
+      pending: report
+        .outgoing
+        .iter()
+        .map(|change| format!("{:?} {}: {}", change.kind, change.collection, change.title))
+        .collect(),
+      last_sync: None,
+    });
+  }
+
+  fn compact(&mut self, _: chrono::DateTime<chrono::Local>) -> std::io::Result<String> {
+    return Ok("http syncer keeps no history, nothing to compact".to_string());
+  }
+
+  fn push_force(&mut self) -> std::io::Result<String> {
+    let mut request = self.new_request(self.all_local_records());
+    request.replace = true;
+    let response = self.send(&request)?;
+
+    self.remember(response.cursor)?;
+    return Ok("push force success".to_string());
+  }
+
+  fn pull_force(&mut self) -> std::io::Result<String> {
+    let mut request = self.new_request(Vec::new());
+    request.cursor = 0;
+    let response = self.send(&request)?;
+
+    self.apply(&response.changes, true, false)?;
+    self.remember(response.cursor)?;
+    return Ok("pull force success".to_string());
+  }
+
+  fn keeps_history(&self) -> bool {
+    return false;
+  }
+
+  fn task_history(&mut self, _: uuid::Uuid) -> std::io::Result<Vec<TaskRevision>> {
+    return Ok(Vec::new());
+  }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use crate::{history::TaskRevision, storage::JsonStorage};
+
+use super::{
+  report::{diff_database_file, SyncReport, SyncStatus},
+  s3_client::{sha256_hex, PutCondition, S3Client},
+  syncer::Syncer,
+};
+
+const STATE_FILENAME: &str = ".s3_sync_state.json";
+/// Copies of the database files as of the last sync, used to tell what was
+/// changed on each side.
+const BASE_FOLDER: &str = ".s3_sync_base";
+
+/// What we knew about a database file after the last successful sync: the
+/// remote ETag and the hash of the local content. Together they tell which
+/// side was changed since then.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct S3FileState {
+  etag: String,
+  hash: String,
+}
+
+pub struct S3Syncer {
+  main_folder_path: String,
+  prefix: String,
+  client: S3Client,
+  state: HashMap<String, S3FileState>,
+}
+
+impl S3Syncer {
+  pub fn new(
+    main_folder_path: &str,
+    endpoint: &str,
+    bucket: &str,
+    prefix: Option<String>,
+    region: Option<String>,
+    access_key: &str,
+    secret_key: &str,
+  ) -> Self {
+    let mut obj = Self {
+      main_folder_path: main_folder_path.to_owned(),
+      prefix: prefix.unwrap_or_default().trim_matches('/').to_owned(),
+      client: S3Client::new(endpoint, bucket, region, access_key, secret_key),
+      state: HashMap::new(),
+    };
+    obj.restore_state();
+    return obj;
+  }
+
+  fn object_key(&self, filename: &str) -> String {
+    if self.prefix.is_empty() {
+      return filename.to_owned();
+    }
+    return format!("{}/{}", self.prefix, filename);
+  }
+
+  fn local_path(&self, filename: &str) -> std::path::PathBuf {
+    std::path::Path::new(&self.main_folder_path).join(filename)
+  }
+
+  fn read_local(&self, filename: &str) -> std::io::Result<Vec<u8>> {
+    match std::fs::read(self.local_path(filename)) {
+      Ok(content) => Ok(content),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+      Err(err) => Err(err),
+    }
+  }
+
+  fn write_local(&self, filename: &str, content: &[u8]) -> std::io::Result<()> {
+    std::fs::write(self.local_path(filename), content)
+  }
+
+  fn read_base(&self, filename: &str) -> Vec<u8> {
+    std::fs::read(self.local_path(BASE_FOLDER).join(filename)).unwrap_or_default()
+  }
+
+  fn restore_state(&mut self) {
+    if let Ok(content) = std::fs::read(self.local_path(STATE_FILENAME)) {
+      self.state = serde_json::from_slice(&content).unwrap_or_default();
+    }
+  }
+
+  fn save_state(&self) -> std::io::Result<()> {
+    let content = serde_json::to_vec_pretty(&self.state)?;
+    self.write_local(STATE_FILENAME, &content)
+  }
+
+  fn remember(&mut self, filename: &str, etag: String, content: &[u8]) -> std::io::Result<()> {
+    self.state.insert(
+      filename.to_owned(),
+      S3FileState {
+        etag,
+        hash: sha256_hex(content),
+      },
+    );
+
+    let base_folder = self.local_path(BASE_FOLDER);
+    std::fs::create_dir_all(&base_folder)?;
+    std::fs::write(base_folder.join(filename), content)
+  }
+
+  fn sync_file(&mut self, filename: &str, report: &mut SyncReport) -> std::io::Result<()> {
+    let key = self.object_key(filename);
+    let local = self.read_local(filename)?;
+    let remote = self.client.get_object(&key)?;
+    let known = self.state.get(filename).cloned();
+    let base = self.read_base(filename);
+
+    let local_changed = match known.as_ref() {
+      Some(state) => state.hash != sha256_hex(&local),
+      None => !is_empty_database(&local),
+    };
+
+    let remote = match remote {
+      None => {
+        let etag = self.client.put_object(&key, &local, PutCondition::IfNoneMatch)?;
+        report
+          .outgoing
+          .extend(diff_database_file(filename, &base, &local));
+        return self.remember(filename, etag, &local);
+      }
+      Some(remote) => remote,
+    };
+
+    let remote_changed = match known.as_ref() {
+      Some(state) => state.etag != remote.etag,
+      None => true,
+    };
+
+    if remote.body == local {
+      return self.remember(filename, remote.etag, &local);
+    }
+
+    match (local_changed, remote_changed) {
+      (false, _) => {
+        self.write_local(filename, &remote.body)?;
+        report
+          .incoming
+          .extend(diff_database_file(filename, &base, &remote.body));
+        self.remember(filename, remote.etag, &remote.body)
+      }
+      (true, false) => {
+        let etag = self
+          .client
+          .put_object(&key, &local, PutCondition::IfMatch(remote.etag))?;
+        report
+          .outgoing
+          .extend(diff_database_file(filename, &base, &local));
+        self.remember(filename, etag, &local)
+      }
+      (true, true) => Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!("{filename} was changed both locally and remotely since the last sync"),
+      )),
+    }
+  }
+}
+
+impl Syncer for S3Syncer {
+  fn commit(&mut self, msg: &str) -> std::io::Result<String> {
+    return Ok(format!("cmd: 'commit', msg: {msg}"));
+  }
+
+  fn sync(&mut self) -> std::io::Result<SyncReport> {
+    let mut report = SyncReport::default();
+    for filename in JsonStorage::FILENAMES {
+      let result = self.sync_file(filename, &mut report);
+      self.save_state()?;
+      result?;
+    }
+    return Ok(report);
+  }
+
+  fn dry_run(&mut self) -> std::io::Result<SyncReport> {
+    let mut report = SyncReport::default();
+    for filename in JsonStorage::FILENAMES {
+      let base = self.read_base(filename);
+      let local = self.read_local(filename)?;
+      report
+        .outgoing
+        .extend(diff_database_file(filename, &base, &local));
+
+      if let Some(remote) = self.client.get_object(&self.object_key(filename))? {
+        report
+          .incoming
+          .extend(diff_database_file(filename, &base, &remote.body));
+      }
+    }
+    return Ok(report);
+  }
+
+  fn status(&mut self) -> std::io::Result<SyncStatus> {
+    let mut status = SyncStatus {
+      remote: Some(self.client.object_url(&self.object_key(""))),
+      ..Default::default()
+    };
+
+    for filename in JsonStorage::FILENAMES {
+      let known = self.state.get(filename).cloned().unwrap_or_default();
+      if sha256_hex(&self.read_local(filename)?) != known.hash {
+        status.ahead += 1;
+        status.pending.push(format!("{filename} changed locally"));
+      }
+
+      let remote = self.client.get_object(&self.object_key(filename))?;
+      if remote.is_some_and(|remote| remote.etag != known.etag) {
+        status.behind += 1;
+      }
+    }
+    return Ok(status);
+  }
+
+  fn compact(&mut self, _: chrono::DateTime<chrono::Local>) -> std::io::Result<String> {
+    return Ok("s3 syncer keeps no history, nothing to compact".to_string());
+  }
+
+  fn push_force(&mut self) -> std::io::Result<String> {
+    for filename in JsonStorage::FILENAMES {
+      let local = self.read_local(filename)?;
+      let etag = self
+        .client
+        .put_object(&self.object_key(filename), &local, PutCondition::None)?;
+      self.remember(filename, etag, &local)?;
+    }
+    self.save_state()?;
+    return Ok("push force success".to_string());
+  }
+
+  fn pull_force(&mut self) -> std::io::Result<String> {
+    for filename in JsonStorage::FILENAMES {
+      if let Some(remote) = self.client.get_object(&self.object_key(filename))? {
+        self.write_local(filename, &remote.body)?;
+        self.remember(filename, remote.etag, &remote.body)?;
+      }
+    }
+    self.save_state()?;
+    return Ok("pull force success".to_string());
+  }
+  fn keeps_history(&self) -> bool {
+    return false;
+  }
+
+  fn task_history(&mut self, _: uuid::Uuid) -> std::io::Result<Vec<TaskRevision>> {
+    return Ok(Vec::new());
+  }
+}
+
+fn is_empty_database(content: &[u8]) -> bool {
+  let trimmed = String::from_utf8_lossy(content);
+  let trimmed = trimmed.trim();
+  return trimmed.is_empty() || trimmed == "[]";
+}
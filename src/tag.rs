@@ -1,9 +1,17 @@
-use crate::traits::Indexable;
+use crate::{
+  billing::Rate,
+  record_meta::RecordMeta,
+  traits::{Indexable, Trackable},
+};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Tag {
   id: uuid::Uuid,
   name: String,
+  #[serde(default)]
+  rate: Option<Rate>,
+  #[serde(flatten)]
+  meta: RecordMeta,
 }
 
 impl Indexable for Tag {
@@ -12,15 +20,31 @@ impl Indexable for Tag {
   }
 }
 
+impl Trackable for Tag {
+  fn meta(&self) -> &RecordMeta {
+    &self.meta
+  }
+
+  fn meta_mut(&mut self) -> &mut RecordMeta {
+    &mut self.meta
+  }
+}
+
 impl Tag {
   pub fn new(name: &str) -> Self {
     Self {
       id: uuid::Uuid::new_v4(),
       name: name.to_owned(),
+      rate: None,
+      meta: RecordMeta::default(),
     }
   }
 
   pub fn name(&self) -> &str {
     self.name.as_str()
   }
+
+  pub fn rate(&self) -> Option<&Rate> {
+    self.rate.as_ref()
+  }
 }
@@ -1,4 +1,29 @@
-use crate::{tag::Tag, time::DateTimeInterval, traits::Indexable};
+use std::collections::BTreeMap;
+
+use crate::{
+  billing::Rate,
+  budget::{BudgetPeriod, BudgetUsage, TimeAmount},
+  duration::{Calendar, Period},
+  fields::{FieldValue, Fields},
+  record_meta::RecordMeta,
+  tag::Tag,
+  time::{DateTimeInterval, DisplayZone},
+  traits::{Indexable, Trackable},
+};
+
+/// Timestamped free text attached to a task.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TaskNote {
+  pub time: chrono::DateTime<chrono::Local>,
+  pub text: String,
+}
+
+/// Optional details given when a task is created.
+#[derive(Debug, Clone, Default)]
+pub struct TaskDetails {
+  pub estimate: Option<chrono::Duration>,
+  pub fields: Fields,
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Task {
@@ -8,6 +33,24 @@ pub struct Task {
   title: String,
   tags: Vec<uuid::Uuid>,
   is_paused: bool,
+  #[serde(default)]
+  notes: Vec<TaskNote>,
+  /// Overrides the tag and project rates.
+  #[serde(default)]
+  rate: Option<Rate>,
+  /// Overrides the project billable flag.
+  #[serde(default)]
+  billable: Option<bool>,
+  #[serde(default)]
+  estimate: Option<TimeAmount>,
+  /// Custom fields, e.g. a ticket or a billing code.
+  #[serde(default)]
+  fields: Fields,
+  /// Issue references found in the title.
+  #[serde(default)]
+  issues: Vec<String>,
+  #[serde(flatten)]
+  meta: RecordMeta,
 }
 
 impl Indexable for Task {
@@ -16,6 +59,16 @@ impl Indexable for Task {
   }
 }
 
+impl Trackable for Task {
+  fn meta(&self) -> &RecordMeta {
+    &self.meta
+  }
+
+  fn meta_mut(&mut self) -> &mut RecordMeta {
+    &mut self.meta
+  }
+}
+
 impl Task {
   pub fn new(
     project_id: uuid::Uuid,
@@ -27,13 +80,20 @@ impl Task {
     Self {
       id: uuid::Uuid::new_v4(),
       project_id,
-      times: vec![DateTimeInterval {
-        start_time: start_time.unwrap_or(chrono::Local::now()),
-        stop_time: finish_time,
-      }],
+      times: vec![DateTimeInterval::new(
+        start_time.unwrap_or(chrono::Local::now()),
+        finish_time,
+      )],
       title: title.to_owned(),
       tags,
       is_paused: false,
+      notes: Vec::new(),
+      rate: None,
+      billable: None,
+      estimate: None,
+      fields: Fields::new(),
+      issues: Vec::new(),
+      meta: RecordMeta::default(),
     }
   }
 
@@ -49,16 +109,77 @@ impl Task {
     &self.tags
   }
 
+  pub fn notes(&self) -> &Vec<TaskNote> {
+    &self.notes
+  }
+
+  pub fn add_note(&mut self, text: &str) {
+    self.notes.push(TaskNote {
+      time: chrono::Local::now(),
+      text: text.to_owned(),
+    });
+  }
+
+  pub fn rate(&self) -> Option<&Rate> {
+    self.rate.as_ref()
+  }
+
+  pub fn billable(&self) -> Option<bool> {
+    self.billable
+  }
+
+  pub fn estimate(&self) -> Option<chrono::Duration> {
+    self.estimate.map(|estimate| estimate.0)
+  }
+
+  /// The same task under another id, e.g. a deterministic one.
+  pub fn with_id(self, id: uuid::Uuid) -> Self {
+    Self { id, ..self }
+  }
+
+  pub fn set_details(&mut self, details: TaskDetails) {
+    self.estimate = details.estimate.map(TimeAmount);
+    self.fields = details.fields;
+  }
+
+  pub fn fields(&self) -> &Fields {
+    &self.fields
+  }
+
+  pub fn field(&self, key: &str) -> Option<&FieldValue> {
+    self.fields.get(key)
+  }
+
+  pub fn issues(&self) -> &Vec<String> {
+    &self.issues
+  }
+
+  pub fn set_issues(&mut self, issues: Vec<String>) {
+    self.issues = issues;
+  }
+
+  pub fn estimate_usage(&self) -> Option<BudgetUsage> {
+    Some(BudgetUsage {
+      limit: self.estimate()?,
+      consumed: self.duration(),
+      period: BudgetPeriod::Total,
+    })
+  }
+
   pub fn times(&self) -> &Vec<DateTimeInterval> {
     &self.times
   }
 
   pub fn start_time(&self) -> chrono::DateTime<chrono::Local> {
-    self.times.first().unwrap().start_time
+    self.times.first().unwrap().start_time.into()
   }
 
   pub fn stop_time(&self) -> Option<chrono::DateTime<chrono::Local>> {
-    self.times.last().unwrap().stop_time
+    self.times.last().unwrap().stop_time.map(|stop_time| stop_time.into())
+  }
+
+  pub fn start_time_in(&self, zone: DisplayZone) -> chrono::DateTime<chrono::FixedOffset> {
+    self.times.first().unwrap().start_in(zone)
   }
 
   pub fn duration(&self) -> chrono::Duration {
@@ -69,8 +190,46 @@ impl Task {
     return total_duration;
   }
 
+  /// The task with its intervals clipped to the period, `None` when it
+  /// wasn't worked on within the period.
+  pub fn clipped(&self, period: &Period) -> Option<Task> {
+    let times: Vec<DateTimeInterval> = self
+      .times
+      .iter()
+      .filter_map(|interval| interval.clip(period.from.to_utc(), period.to.to_utc(), period.open_ended))
+      .collect();
+    match times.is_empty() {
+      true => None,
+      false => Some(Self {
+        times,
+        ..self.clone()
+      }),
+    }
+  }
+
+  /// Parts of the task per working day, each with the intervals of that day.
+  pub fn split_by_day(&self, calendar: &Calendar, zone: DisplayZone) -> Vec<(chrono::NaiveDate, Task)> {
+    let mut times_by_day: BTreeMap<chrono::NaiveDate, Vec<DateTimeInterval>> = BTreeMap::new();
+    for interval in self.times.iter() {
+      for (date, part) in calendar.split_by_day(interval, zone) {
+        times_by_day.entry(date).or_default().push(part);
+      }
+    }
+
+    times_by_day
+      .into_iter()
+      .map(|(date, times)| {
+        let task = Self {
+          times,
+          ..self.clone()
+        };
+        (date, task)
+      })
+      .collect()
+  }
+
   pub fn stop(&mut self) {
-    self.times.last_mut().unwrap().stop_time = Some(chrono::Local::now());
+    self.times.last_mut().unwrap().stop_time = Some(chrono::Utc::now());
     self.is_paused = false;
   }
 
@@ -84,10 +243,7 @@ impl Task {
   }
 
   pub fn resume(&mut self) {
-    self.times.push(DateTimeInterval {
-      start_time: chrono::Local::now(),
-      stop_time: None,
-    });
+    self.times.push(DateTimeInterval::new(chrono::Local::now(), None));
     self.is_paused = false;
   }
 }
@@ -100,6 +256,21 @@ pub struct TaskView {
   title: String,
   tags: Vec<String>,
   is_paused: bool,
+  #[serde(default)]
+  notes: Vec<TaskNote>,
+  /// Overrides the tag and project rates.
+  #[serde(default)]
+  rate: Option<Rate>,
+  /// Overrides the project billable flag.
+  #[serde(default)]
+  billable: Option<bool>,
+  #[serde(default)]
+  estimate: Option<TimeAmount>,
+  /// Custom fields, e.g. a ticket or a billing code.
+  #[serde(default)]
+  fields: Fields,
+  #[serde(flatten)]
+  meta: RecordMeta,
 }
 
 impl TaskView {
@@ -115,6 +286,12 @@ impl TaskView {
         .map(|tag| tag.name().to_owned())
         .collect(),
       is_paused: task.is_paused(),
+      notes: task.notes().clone(),
+      rate: task.rate().cloned(),
+      billable: task.billable(),
+      estimate: task.estimate().map(TimeAmount),
+      fields: task.fields().clone(),
+      meta: task.meta().clone(),
     }
   }
 
@@ -152,6 +329,13 @@ impl TaskView {
       title: self.title.clone(),
       tags: tag_ids,
       is_paused: self.is_paused,
+      notes: self.notes.clone(),
+      rate: self.rate.clone(),
+      billable: self.billable,
+      estimate: self.estimate,
+      fields: self.fields.clone(),
+      issues: Vec::new(),
+      meta: self.meta.clone(),
     }
   }
 }
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use crate::fields::Fields;
+
+pub const TEMPLATE_PREFIX: char = '@';
+
+/// A task started often, e.g. a daily standup. Used as `busy start @name`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TaskTemplate {
+  pub project: String,
+  pub title: String,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  #[serde(default)]
+  pub fields: Fields,
+}
+
+/// The template name if the argument refers to one, e.g. `@standup`.
+pub fn template_name(arg: &str) -> Option<&str> {
+  arg
+    .strip_prefix(TEMPLATE_PREFIX)
+    .filter(|name| !name.is_empty())
+}
+
+pub fn find_template(templates: &BTreeMap<String, TaskTemplate>, name: &str) -> Result<TaskTemplate, String> {
+  templates
+    .get(name)
+    .cloned()
+    .ok_or(format!("template '{name}' not found"))
+}
+
+/// Project, title, tags and fields of a new task, given directly or taken
+/// from a template. Arguments after a template are left in `extra`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskInput {
+  pub project_name: String,
+  pub title: String,
+  pub tags: Vec<String>,
+  pub fields: Fields,
+  pub extra: Vec<String>,
+}
+
+impl TaskInput {
+  /// Start and finish time given after a template, `busy add @review 10:00 10:30`.
+  pub fn add_times(&self) -> Result<(Option<&String>, Option<&String>), String> {
+    if self.extra.len() > 2 {
+      return Err("expected only start and finish time after the template".to_string());
+    }
+    return Ok((self.extra.first(), self.extra.get(1)));
+  }
+}
+
+pub fn resolve_task_input(
+  templates: &BTreeMap<String, TaskTemplate>,
+  project_name: &str,
+  task_title: Option<&String>,
+  tags: &[String],
+) -> Result<TaskInput, String> {
+  if let Some(name) = template_name(project_name) {
+    let template = find_template(templates, name)?;
+    return Ok(TaskInput {
+      project_name: template.project,
+      title: template.title,
+      tags: template.tags,
+      fields: template.fields,
+      extra: task_title.into_iter().chain(tags).cloned().collect(),
+    });
+  }
+
+  return Ok(TaskInput {
+    project_name: project_name.to_owned(),
+    title: task_title.ok_or("task title is required")?.to_owned(),
+    tags: tags.to_vec(),
+    fields: Fields::new(),
+    extra: Vec::new(),
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::{resolve_task_input, TaskTemplate};
+  use crate::fields::FieldValue;
+
+  fn templates() -> BTreeMap<String, TaskTemplate> {
+    let template: TaskTemplate = serde_json::from_str(
+      r#"{"project": "team", "title": "daily standup", "tags": ["meeting"], "fields": {"po": 7}}"#,
+    )
+    .unwrap();
+    return BTreeMap::from([("standup".to_string(), template)]);
+  }
+
+  fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+  }
+
+  #[test]
+  fn test_template_with_extra_tags() {
+    let extra = strings(&["sync", "remote"]);
+
+    let input = resolve_task_input(&templates(), "@standup", Some(&extra[0]), &extra[1..]).unwrap();
+
+    assert_eq!(input.project_name, "team");
+    assert_eq!(input.title, "daily standup");
+    assert_eq!(input.tags, strings(&["meeting"]));
+    assert_eq!(input.fields["po"], FieldValue::Integer(7));
+    assert_eq!(input.extra, strings(&["sync", "remote"]));
+  }
+
+  #[test]
+  fn test_template_with_add_times() {
+    let extra = strings(&["10:00", "10:30"]);
+
+    let input = resolve_task_input(&templates(), "@standup", Some(&extra[0]), &extra[1..]).unwrap();
+
+    assert_eq!(input.add_times(), Ok((Some(&extra[0]), Some(&extra[1]))));
+
+    let extra = strings(&["10:00", "10:30", "11:00"]);
+    let input = resolve_task_input(&templates(), "@standup", Some(&extra[0]), &extra[1..]).unwrap();
+    assert!(input.add_times().is_err());
+  }
+
+  #[test]
+  fn test_unknown_template_and_plain_input() {
+    let error = resolve_task_input(&templates(), "@review", None, &[]).unwrap_err();
+    assert_eq!(error, "template 'review' not found");
+
+    let title = "fix login".to_string();
+    let input = resolve_task_input(&templates(), "web", Some(&title), &strings(&["bug"])).unwrap();
+    assert_eq!(input.project_name, "web");
+    assert_eq!(input.tags, strings(&["bug"]));
+    assert!(input.extra.is_empty());
+    assert!(resolve_task_input(&templates(), "web", None, &[]).is_err());
+  }
+}
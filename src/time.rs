@@ -1,31 +1,244 @@
-use chrono::{ParseResult, TimeZone};
+use chrono::{Datelike, Offset, TimeZone};
 
+use crate::duration::parse_duration;
+
+/// Which zone recorded moments are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum DisplayZone {
+  /// The zone of this machine, the same instant looks the same in all tasks.
+  #[default]
+  Current,
+  /// The zone the interval was recorded in, e.g. the local time of a trip.
+  Recorded,
+}
+
+/// A time interval stored as UTC instants plus the UTC offset of the zone it
+/// was recorded in, so it doesn't move when the machine zone changes.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(from = "StoredDateTimeInterval")]
 pub struct DateTimeInterval {
-  pub start_time: chrono::DateTime<chrono::Local>,
-  pub stop_time: Option<chrono::DateTime<chrono::Local>>,
+  pub start_time: chrono::DateTime<chrono::Utc>,
+  pub stop_time: Option<chrono::DateTime<chrono::Utc>>,
+  #[serde(serialize_with = "serialize_offset")]
+  pub recorded_offset: chrono::FixedOffset,
+}
+
+/// Intervals were stored as local times with an offset before, the offset
+/// of the start time is the recorded one for them.
+#[derive(serde::Deserialize)]
+struct StoredDateTimeInterval {
+  start_time: chrono::DateTime<chrono::FixedOffset>,
+  stop_time: Option<chrono::DateTime<chrono::FixedOffset>>,
+  #[serde(default, deserialize_with = "deserialize_offset")]
+  recorded_offset: Option<chrono::FixedOffset>,
+}
+
+impl From<StoredDateTimeInterval> for DateTimeInterval {
+  fn from(stored: StoredDateTimeInterval) -> Self {
+    Self {
+      start_time: stored.start_time.to_utc(),
+      stop_time: stored.stop_time.map(|stop_time| stop_time.to_utc()),
+      recorded_offset: stored
+        .recorded_offset
+        .unwrap_or(*stored.start_time.offset()),
+    }
+  }
 }
 
 impl DateTimeInterval {
+  /// Records the interval in the current zone.
+  pub fn new(
+    start_time: chrono::DateTime<chrono::Local>,
+    stop_time: Option<chrono::DateTime<chrono::Local>>,
+  ) -> Self {
+    Self {
+      start_time: start_time.to_utc(),
+      stop_time: stop_time.map(|stop_time| stop_time.to_utc()),
+      recorded_offset: start_time.offset().fix(),
+    }
+  }
+
   pub fn duration(&self) -> chrono::Duration {
     return self
       .stop_time
-      .unwrap_or(chrono::Local::now())
+      .unwrap_or(chrono::Utc::now())
       .signed_duration_since(self.start_time);
   }
+
+  /// The part of the interval within `[from, to)`. A running interval stays
+  /// running when the range is `open_ended`.
+  pub fn clip(
+    &self,
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+    open_ended: bool,
+  ) -> Option<Self> {
+    let start_time = self.start_time.max(from);
+    let stop_time = match self.stop_time {
+      Some(stop_time) => Some(stop_time.min(to)),
+      None if open_ended => None,
+      None => Some(to),
+    };
+    if start_time >= stop_time.unwrap_or(to) {
+      return None;
+    }
+
+    Some(Self {
+      start_time,
+      stop_time,
+      recorded_offset: self.recorded_offset,
+    })
+  }
+
+  pub fn start_in(&self, zone: DisplayZone) -> chrono::DateTime<chrono::FixedOffset> {
+    self.in_zone(self.start_time, zone)
+  }
+
+  pub fn stop_in(&self, zone: DisplayZone) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    self.stop_time.map(|stop_time| self.in_zone(stop_time, zone))
+  }
+
+  fn in_zone(
+    &self,
+    moment: chrono::DateTime<chrono::Utc>,
+    zone: DisplayZone,
+  ) -> chrono::DateTime<chrono::FixedOffset> {
+    match zone {
+      DisplayZone::Current => moment.with_timezone(&chrono::Local).fixed_offset(),
+      DisplayZone::Recorded => moment.with_timezone(&self.recorded_offset),
+    }
+  }
 }
 
-pub fn parse_datetime(datetime: &str) -> ParseResult<chrono::DateTime<chrono::Local>> {
-  let mut input = datetime.to_owned();
-  if !input.contains(' ') {
-    input = format!("{} {}", chrono::Local::now().format("%Y-%m-%d"), input);
+fn serialize_offset<S: serde::Serializer>(
+  offset: &chrono::FixedOffset,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serializer.serialize_str(&offset.to_string())
+}
+
+fn deserialize_offset<'de, D: serde::Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<chrono::FixedOffset>, D::Error> {
+  let offset: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+  offset
+    .map(|offset| offset.parse().map_err(serde::de::Error::custom))
+    .transpose()
+}
+
+/// Forms accepted by `parse_datetime`, shown when the input can't be parsed.
+pub const ACCEPTED_DATETIME_FORMS: &str = "now, HH:MM[:SS], YYYY-mm-dd [HH:MM[:SS]], \
+ISO 8601 (2024-01-31T14:00:00+01:00), today|yesterday|tomorrow [HH:MM], \
+weekday [HH:MM] (mon, monday), 15m ago, -1h30m, +10m, in 10m";
+
+/// Parses an absolute or relative moment, see `ACCEPTED_DATETIME_FORMS`.
+pub fn parse_datetime(datetime: &str) -> Result<chrono::DateTime<chrono::Local>, String> {
+  parse_datetime_relative_to(datetime, chrono::Local::now())
+}
+
+/// Same as `parse_datetime`, relative forms are resolved against `now`.
+pub fn parse_datetime_relative_to(
+  datetime: &str,
+  now: chrono::DateTime<chrono::Local>,
+) -> Result<chrono::DateTime<chrono::Local>, String> {
+  let input = datetime.trim().to_lowercase();
+  let parse_err = || format!("can't parse '{datetime}', accepted forms: {ACCEPTED_DATETIME_FORMS}");
+
+  if input == "now" {
+    return Ok(now);
+  }
+  if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(&input.to_uppercase()) {
+    return Ok(datetime.with_timezone(&chrono::Local));
   }
-  return chrono::Local.datetime_from_str(&input, "%Y-%m-%d %H:%M");
+  if let Some(offset) = parse_relative_offset(&input) {
+    return now
+      .checked_add_signed(offset)
+      .ok_or(format!("'{datetime}' is out of range"));
+  }
+
+  let (day, time) = match input.split_once(' ').or(iso_date_and_time(&input)) {
+    Some((day, time)) => (day, Some(time.trim())),
+    None if input.contains(':') => ("today", Some(input.as_str())),
+    None => (input.as_str(), None),
+  };
+  let date = resolve_date(day, now.date_naive()).ok_or_else(parse_err)?;
+  let time = match time {
+    Some(time) => parse_time(time).ok_or_else(parse_err)?,
+    None => chrono::NaiveTime::MIN,
+  };
+
+  return chrono::Local
+    .from_local_datetime(&date.and_time(time))
+    .earliest()
+    .ok_or(format!("'{datetime}' doesn't exist in the local time zone"));
+}
+
+/// Splits ISO 8601 `2024-01-31t14:00` without an offset into date and time.
+fn iso_date_and_time(input: &str) -> Option<(&str, &str)> {
+  let (day, time) = input.split_once('t')?;
+  match day.len() == "yyyy-mm-dd".len() {
+    true => Some((day, time)),
+    false => None,
+  }
+}
+
+/// `15m ago`, `-1h30m`, `+10m` or `in 10m`.
+fn parse_relative_offset(input: &str) -> Option<chrono::Duration> {
+  if let Some(duration) = input.strip_suffix("ago") {
+    return parse_duration(duration).ok().map(|duration| -duration);
+  }
+  if let Some(duration) = input.strip_prefix('-') {
+    return parse_duration(duration).ok().map(|duration| -duration);
+  }
+  if let Some(duration) = input.strip_prefix('+').or(input.strip_prefix("in ")) {
+    return parse_duration(duration).ok();
+  }
+  return None;
+}
+
+/// Parses a day: `YYYY-mm-dd`, today|yesterday|tomorrow or a weekday name for
+/// the latest such day, today included.
+pub fn parse_date(date: &str, today: chrono::NaiveDate) -> Result<chrono::NaiveDate, String> {
+  resolve_date(date.trim().to_lowercase().as_str(), today).ok_or(format!(
+    "can't parse date '{date}', accepted forms: YYYY-mm-dd, today|yesterday|tomorrow, weekday (mon, monday)"
+  ))
+}
+
+fn resolve_date(day: &str, today: chrono::NaiveDate) -> Option<chrono::NaiveDate> {
+  match day {
+    "today" => return Some(today),
+    "yesterday" => return today.pred_opt(),
+    "tomorrow" => return today.succ_opt(),
+    _ => {}
+  }
+  if let Ok(weekday) = day.parse::<chrono::Weekday>() {
+    // The latest such day, today included.
+    let days_back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    return today.checked_sub_days(chrono::Days::new(days_back.into()));
+  }
+  return chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d").ok();
+}
+
+fn parse_time(time: &str) -> Option<chrono::NaiveTime> {
+  chrono::NaiveTime::parse_from_str(time, "%H:%M:%S")
+    .or_else(|_| chrono::NaiveTime::parse_from_str(time, "%H:%M"))
+    .ok()
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::time::parse_datetime;
+  use chrono::TimeZone;
+
+  use crate::time::{parse_datetime, parse_datetime_relative_to, DateTimeInterval, DisplayZone};
+
+  fn parse_at_fixed_now(input: &str) -> String {
+    // Wednesday
+    let now = chrono::Local.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+    parse_datetime_relative_to(input, now)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S")
+      .to_string()
+  }
 
   #[test]
   fn test_parse_datetime() {
@@ -43,4 +256,86 @@ mod tests {
       format!("{} {}", chrono::Local::now().format("%Y-%m-%d"), "11:00")
     );
   }
+
+  #[test]
+  fn test_interval_keeps_recorded_offset() {
+    let interval: DateTimeInterval = serde_json::from_str(
+      r#"{"start_time": "2024-01-31T09:00:00+03:00", "stop_time": "2024-01-31T10:00:00+03:00"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(interval.start_time.to_string(), "2024-01-31 06:00:00 UTC");
+    assert_eq!(interval.recorded_offset.to_string(), "+03:00");
+    assert_eq!(
+      interval.start_in(DisplayZone::Recorded).to_rfc3339(),
+      "2024-01-31T09:00:00+03:00"
+    );
+
+    let stored = serde_json::to_value(&interval).unwrap();
+    assert_eq!(stored["start_time"], "2024-01-31T06:00:00Z");
+    assert_eq!(stored["recorded_offset"], "+03:00");
+    let restored: DateTimeInterval = serde_json::from_value(stored).unwrap();
+    assert_eq!(restored.recorded_offset, interval.recorded_offset);
+    assert_eq!(restored.stop_time, interval.stop_time);
+  }
+
+  #[test]
+  fn test_clip_interval() {
+    let moment = |time: &str| time.parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+    let interval: DateTimeInterval = serde_json::from_str(
+      r#"{"start_time": "2024-01-31T09:00:00Z", "stop_time": "2024-01-31T11:00:00Z"}"#,
+    )
+    .unwrap();
+
+    let clipped = interval
+      .clip(moment("2024-01-31T10:00:00Z"), moment("2024-02-01T00:00:00Z"), false)
+      .unwrap();
+    assert_eq!(clipped.start_time, moment("2024-01-31T10:00:00Z"));
+    assert_eq!(clipped.stop_time, Some(moment("2024-01-31T11:00:00Z")));
+
+    let outside = interval.clip(moment("2024-01-31T11:00:00Z"), moment("2024-02-01T00:00:00Z"), false);
+    assert!(outside.is_none());
+
+    let running = DateTimeInterval {
+      stop_time: None,
+      ..interval.clone()
+    };
+    let clipped_running = running
+      .clip(moment("2024-01-31T00:00:00Z"), moment("2024-02-01T00:00:00Z"), false)
+      .unwrap();
+    assert_eq!(clipped_running.stop_time, Some(moment("2024-02-01T00:00:00Z")));
+    let still_running = running
+      .clip(moment("2024-01-31T00:00:00Z"), moment("2024-02-01T00:00:00Z"), true)
+      .unwrap();
+    assert_eq!(still_running.stop_time, None);
+  }
+
+  #[test]
+  fn test_parse_relative_datetime() {
+    assert_eq!(parse_at_fixed_now("now"), "2024-01-31 12:00:00");
+    assert_eq!(parse_at_fixed_now("15m ago"), "2024-01-31 11:45:00");
+    assert_eq!(parse_at_fixed_now("-1h30m"), "2024-01-31 10:30:00");
+    assert_eq!(parse_at_fixed_now("in 10m"), "2024-01-31 12:10:00");
+    assert_eq!(parse_at_fixed_now("yesterday 14:00"), "2024-01-30 14:00:00");
+    assert_eq!(parse_at_fixed_now("tomorrow"), "2024-02-01 00:00:00");
+    assert_eq!(parse_at_fixed_now("mon 9:00"), "2024-01-29 09:00:00");
+    assert_eq!(parse_at_fixed_now("Wednesday 9:00"), "2024-01-31 09:00:00");
+  }
+
+  #[test]
+  fn test_parse_datetime_with_seconds_and_iso() {
+    assert_eq!(parse_at_fixed_now("10:15:30"), "2024-01-31 10:15:30");
+    assert_eq!(parse_at_fixed_now("2020-01-01 10:15:30"), "2020-01-01 10:15:30");
+    assert_eq!(parse_at_fixed_now("2020-01-01T10:15"), "2020-01-01 10:15:00");
+
+    let utc = parse_datetime("2020-01-01T10:15:00Z").unwrap();
+    assert_eq!(utc.naive_utc().to_string(), "2020-01-01 10:15:00");
+  }
+
+  #[test]
+  fn test_parse_datetime_error_lists_accepted_forms() {
+    let err = parse_datetime("soon").unwrap_err();
+    assert!(err.contains("'soon'"));
+    assert!(err.contains("15m ago"));
+  }
 }
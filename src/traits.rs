@@ -1,3 +1,10 @@
+use crate::record_meta::RecordMeta;
+
 pub trait Indexable {
   fn id(&self) -> uuid::Uuid;
 }
+
+pub trait Trackable {
+  fn meta(&self) -> &RecordMeta;
+  fn meta_mut(&mut self) -> &mut RecordMeta;
+}